@@ -0,0 +1,189 @@
+use serde_json::Value;
+use soducant_core::sudoku::sudoku::Board;
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter};
+use std::process::ExitCode;
+use std::sync::{Mutex, mpsc};
+use std::thread;
+use std::time::Instant;
+
+use crate::input::parse_puzzle;
+use crate::output::{Format, Printer, Record, board_to_string};
+use crate::{BatchArgs, EXIT_FAILURE, SolverArgs, solve_board};
+
+struct Puzzle {
+    /// 1-based line number in the input
+    line: usize,
+    text: String,
+}
+
+type Outcome = Result<Board, String>;
+
+fn open_input(path: Option<&str>) -> Result<Box<dyn BufRead + Send>, Box<dyn std::error::Error>> {
+    match path {
+        Some(path) if path != "-" => {
+            let file = File::open(path).map_err(|e| format!("Couldn't read {}: {}", path, e))?;
+            return Ok(Box::new(BufReader::new(file)));
+        }
+        _ => return Ok(Box::new(BufReader::new(std::io::stdin()))),
+    }
+}
+
+/// Plain output keeps one line per puzzle, leaving a blank line for failures
+fn outcome_record(puzzle: &Puzzle, outcome: &Outcome) -> Record {
+    let record = Record::new()
+        .field("line", puzzle.line)
+        .field("puzzle", puzzle.text.clone());
+    match outcome {
+        Ok(solved) => record
+            .field("solution", solved.to_clean_string())
+            .field("error", Value::Null)
+            .plain(solved.to_clean_string())
+            .grid(format!(
+                "Line {}:\n{}",
+                puzzle.line,
+                board_to_string(solved, Format::Grid, '0')
+            )),
+        Err(e) => record
+            .field("solution", Value::Null)
+            .field("error", e.clone())
+            .plain("")
+            .grid(format!(
+                "Line {}: couldn't solve sudoku: {}",
+                puzzle.line, e
+            )),
+    }
+}
+
+/// Holds back results that finish early, releasing them in input order
+struct InOrder<T> {
+    next: usize,
+    pending: BTreeMap<usize, T>,
+}
+
+impl<T> InOrder<T> {
+    fn new() -> Self {
+        InOrder {
+            next: 0,
+            pending: BTreeMap::new(),
+        }
+    }
+
+    /// Takes the result for `index`, returning every result whose turn has now come
+    fn push(&mut self, index: usize, item: T) -> Vec<T> {
+        self.pending.insert(index, item);
+        let mut ready = Vec::new();
+        while let Some(item) = self.pending.remove(&self.next) {
+            ready.push(item);
+            self.next += 1;
+        }
+        return ready;
+    }
+}
+
+fn solve_one(puzzle: &Puzzle, index: usize, solver: &SolverArgs) -> Outcome {
+    // Offset the seed so results don't depend on which worker picks up the puzzle
+    let mut solver = solver.clone();
+    solver.seed = solver.seed.map(|seed| seed.wrapping_add(index as u64));
+    return parse_puzzle(&puzzle.text)
+        .and_then(|board| solve_board(&board, &solver))
+        .map_err(|e| e.to_string());
+}
+
+pub fn run_batch(args: BatchArgs) -> Result<ExitCode, Box<dyn std::error::Error>> {
+    let input = open_input(args.input.as_deref())?;
+
+    let start = Instant::now();
+    let (work_tx, work_rx) = mpsc::channel::<(usize, Puzzle)>();
+    let work_rx = Mutex::new(work_rx);
+    let (tx, rx) = mpsc::channel::<(usize, Puzzle, Outcome)>();
+    let mut failures: Vec<(usize, String)> = Vec::new();
+    let mut total = 0;
+
+    thread::scope(|scope| -> std::io::Result<()> {
+        // Hand puzzles to the workers as lines come in, rather than waiting for the whole input
+        let reader = scope.spawn(move || -> std::io::Result<()> {
+            let mut index = 0;
+            for (i, line) in input.lines().enumerate() {
+                let text = line?.trim().to_string();
+                if text.is_empty() || text.starts_with('#') {
+                    continue;
+                }
+                if work_tx.send((index, Puzzle { line: i + 1, text })).is_err() {
+                    break;
+                }
+                index += 1;
+            }
+            return Ok(());
+        });
+
+        for _ in 0..args.jobs.max(1) {
+            let tx = tx.clone();
+            let (work_rx, solver) = (&work_rx, &args.solver);
+            scope.spawn(move || {
+                loop {
+                    let Ok((index, puzzle)) = work_rx.lock().unwrap().recv() else {
+                        break;
+                    };
+                    let outcome = solve_one(&puzzle, index, solver);
+                    if tx.send((index, puzzle, outcome)).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(tx);
+
+        // Workers finish out of order, so hold results back until their turn comes
+        let mut printer = Printer::new(args.format, BufWriter::new(std::io::stdout().lock()));
+        let mut in_order = InOrder::new();
+        for (index, puzzle, outcome) in rx {
+            let ready = in_order.push(index, (puzzle, outcome));
+            for (puzzle, outcome) in &ready {
+                printer.print(&outcome_record(puzzle, outcome))?;
+                if let Err(e) = outcome {
+                    failures.push((puzzle.line, e.clone()));
+                }
+                total += 1;
+            }
+            // Input may trickle in, so show each result as soon as its turn comes
+            if !ready.is_empty() {
+                printer.flush()?;
+            }
+        }
+        return reader.join().expect("the input reader doesn't panic");
+    })?;
+
+    eprintln!(
+        "Solved {}/{} puzzles in {:?}",
+        total - failures.len(),
+        total,
+        start.elapsed()
+    );
+    for (line, e) in &failures {
+        eprintln!("  line {}: {}", line, e);
+    }
+
+    if !failures.is_empty() {
+        return Ok(ExitCode::from(EXIT_FAILURE));
+    }
+    return Ok(ExitCode::SUCCESS);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::InOrder;
+
+    #[test]
+    fn releases_results_in_input_order() {
+        let mut in_order = InOrder::new();
+        assert!(in_order.push(2, 'c').is_empty());
+        assert!(in_order.push(1, 'b').is_empty());
+        assert_eq!(in_order.push(0, 'a'), ['a', 'b', 'c']);
+        assert_eq!(in_order.push(3, 'd'), ['d']);
+        assert!(in_order.push(5, 'f').is_empty());
+        assert_eq!(in_order.push(4, 'e'), ['e', 'f']);
+    }
+}
@@ -0,0 +1,196 @@
+use soducant_core::multigrid::multigrid::MultiBoard;
+use soducant_core::rules::rules::apply_rules;
+use soducant_core::sudoku::sudoku::{BOARD_SIZE, Board, Regions};
+
+use std::io::Read;
+
+/// Reads a puzzle from the argument, or from stdin when it is missing or `-`
+pub fn read_puzzle(arg: Option<&str>) -> Result<Board, Box<dyn std::error::Error>> {
+    return parse_puzzle(&read_text(arg)?);
+}
+
+/// Reads a samurai from the argument or stdin, as the 21-line layout or five 81-character grids
+pub fn read_multi_puzzle(arg: Option<&str>) -> Result<MultiBoard, Box<dyn std::error::Error>> {
+    return read_text(arg)?.parse();
+}
+
+fn read_text(arg: Option<&str>) -> Result<String, Box<dyn std::error::Error>> {
+    return match arg {
+        Some(text) if text != "-" => Ok(text.to_string()),
+        _ => {
+            let mut text = String::new();
+            std::io::stdin().read_to_string(&mut text)?;
+            Ok(text)
+        }
+    };
+}
+
+/// Accepts an 81-character string (digits, `0` or `.` for empty, whitespace ignored) or the
+/// box-drawing grid printed by `Board`'s `Display`. Variant rules follow on their own lines, as
+/// read by [`apply_rules`]. With only rules the board starts empty.
+pub fn parse_puzzle(text: &str) -> Result<Board, Box<dyn std::error::Error>> {
+    let (rules, digits): (Vec<&str>, Vec<&str>) = text.lines().partition(|line| {
+        line.trim_start()
+            .starts_with(|c: char| c.is_ascii_alphabetic())
+    });
+
+    let digits = digits.join("\n");
+    let mut board = if digits.contains('║') {
+        parse_grid(&digits)?
+    } else {
+        let compact: String = digits.chars().filter(|c| !c.is_whitespace()).collect();
+        if compact.is_empty() && !rules.is_empty() {
+            Board::default()
+        } else {
+            Board::from_string(&compact)?
+        }
+    };
+    apply_rules(&mut board, rules)?;
+    return Ok(board);
+}
+
+fn parse_grid(text: &str) -> Result<Board, Box<dyn std::error::Error>> {
+    let body: Vec<Vec<char>> = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| line.starts_with('║'))
+        .map(|line| line.trim_matches('║').replace(" ⁞", "").chars().collect())
+        .collect();
+    // Jigsaw grids put a border line between every pair of rows
+    let jigsaw = body.len() == 2 * BOARD_SIZE - 1 && !text.contains('⋯');
+    let rows: Vec<&Vec<char>> = if jigsaw {
+        body.iter().step_by(2).collect()
+    } else {
+        body.iter().filter(|line| !line.contains(&'⋯')).collect()
+    };
+
+    let mut compact = String::new();
+    for cells in &rows {
+        for j in 0..BOARD_SIZE {
+            match cells.get(2 * j + 1) {
+                Some(' ') => compact.push('0'),
+                Some(c) => compact.push(*c),
+                None => {
+                    let line: String = cells.iter().collect();
+                    return Err(Box::from(format!("Grid row is too short: {}", line)));
+                }
+            }
+        }
+    }
+    let mut board = Board::from_string(&compact)?;
+    // Sudoku X boards mark their diagonals
+    board.set_diagonal(text.contains(['╲', '╱', '╳']));
+    if jigsaw {
+        let borders: Vec<&Vec<char>> = body.iter().skip(1).step_by(2).collect();
+        board.set_regions(regions_from_borders(&rows, &borders)?);
+    }
+    return Ok(board);
+}
+
+/// Rebuilds jigsaw regions from the `│` and `─` borders drawn between cells
+fn regions_from_borders(
+    rows: &[&Vec<char>],
+    borders: &[&Vec<char>],
+) -> Result<Regions, Box<dyn std::error::Error>> {
+    let mut map = [[u8::MAX; BOARD_SIZE]; BOARD_SIZE];
+    let mut next: u8 = 0;
+    for start in 0..BOARD_SIZE * BOARD_SIZE {
+        if map[start / BOARD_SIZE][start % BOARD_SIZE] != u8::MAX {
+            continue;
+        }
+        if next as usize == BOARD_SIZE {
+            return Err(Box::from("Grid has more than nine regions"));
+        }
+        let mut stack = vec![(start / BOARD_SIZE, start % BOARD_SIZE)];
+        while let Some((i, j)) = stack.pop() {
+            if map[i][j] != u8::MAX {
+                continue;
+            }
+            map[i][j] = next;
+            let open_left = |i: usize, j: usize| rows[i].get(2 * j) != Some(&'│');
+            let open_up = |i: usize, j: usize| borders[i - 1].get(2 * j + 1) != Some(&'─');
+            if j > 0 && open_left(i, j) {
+                stack.push((i, j - 1));
+            }
+            if j + 1 < BOARD_SIZE && open_left(i, j + 1) {
+                stack.push((i, j + 1));
+            }
+            if i > 0 && open_up(i, j) {
+                stack.push((i - 1, j));
+            }
+            if i + 1 < BOARD_SIZE && open_up(i + 1, j) {
+                stack.push((i + 1, j));
+            }
+        }
+        next += 1;
+    }
+    return Regions::from_map(map);
+}
+
+#[cfg(test)]
+mod tests {
+    use soducant_core::rules::rules::rule_lines;
+    use soducant_core::sudoku::sudoku::Board;
+
+    use super::parse_puzzle;
+
+    const PUZZLE: &str =
+        "278000401609100050005006900430809000706003000091000800000020173860001004107934685";
+
+    #[test]
+    fn parses_printed_grids() {
+        let board = Board::from_string(PUZZLE).unwrap();
+        let parsed = parse_puzzle(&board.to_string()).unwrap();
+        assert_eq!(parsed.to_clean_string(), PUZZLE);
+        assert!(!parsed.is_diagonal());
+        assert!(parsed.regions().is_boxes());
+
+        let mut board = Board::from_string(PUZZLE).unwrap();
+        board.set_diagonal(true);
+        let parsed = parse_puzzle(&board.to_string()).unwrap();
+        assert_eq!(parsed.to_clean_string(), PUZZLE);
+        assert!(parsed.is_diagonal());
+
+        // The jigsaw from the exact solver's tests
+        let mut board = Board::from_string(
+            "020450709078061004001000402700092000000100000306245100050024800000039000007008020",
+        )
+        .unwrap();
+        board.set_regions(
+            "412223333411122233411122663441526633441555666445585996777785996777785999788888899"
+                .parse()
+                .unwrap(),
+        );
+        let parsed = parse_puzzle(&board.to_string()).unwrap();
+        assert_eq!(parsed.to_clean_string(), board.to_clean_string());
+        assert_eq!(parsed.regions(), board.regions());
+
+        let error = parse_puzzle("║ 1 2 3 ║").unwrap_err();
+        assert_eq!(error.to_string(), "Grid row is too short:  1 2 3 ");
+    }
+
+    #[test]
+    fn parses_rule_lines() {
+        let rules = [
+            "cage 10 r9c1 r9c2",
+            "white r8c8 r8c9",
+            "negative kropki",
+            "extra r1c1 r1c2 r1c3 r1c4 r1c5 r1c6 r1c7 r1c8 r1c9",
+        ];
+        let board = parse_puzzle(&format!("{}\n{}", PUZZLE, rules.join("\n"))).unwrap();
+        assert_eq!(board.to_clean_string(), PUZZLE);
+        assert_eq!(board.cages().len(), 1);
+        assert_eq!(board.extra_regions().len(), 1);
+        let mut lines = rule_lines(&board);
+        lines.sort();
+        let mut expected = rules.map(String::from).to_vec();
+        expected.sort();
+        assert_eq!(lines, expected);
+
+        // Rules alone start from an empty board
+        let board = parse_puzzle("  negative xv\n").unwrap();
+        assert_eq!(board.to_clean_string(), "0".repeat(81));
+        assert!(parse_puzzle("cage ten r1c1").is_err());
+        assert!(parse_puzzle(&format!("{}\nnegative everything", PUZZLE)).is_err());
+    }
+}
@@ -1,57 +1,751 @@
+#![forbid(unsafe_code)]
+#![allow(clippy::needless_return)]
+
+mod batch;
+mod input;
+mod output;
+mod play;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use serde_json::Value;
+use soducant_core::constraint::constraint::Candidates;
+use soducant_core::exact::exact;
+use soducant_core::generator::generator::generate;
+use soducant_core::hint::hint::hint;
+use soducant_core::logic::logic::{LogicOptions, Step, Technique, solve_logically_with};
+use soducant_core::multigrid::multigrid;
+use soducant_core::rating::rating::rate;
+use soducant_core::rules::rules::rule_lines;
 use soducant_core::solver;
-use soducant_core::solver::solver::Solver;
-use soducant_core::sudoku;
-use soducant_core::sudoku::sudoku::Board;
-use soducant_core::sudoku::sudoku::Solution;
-use std::env;
-
-fn main() {
-    if let Some(cmd) = env::args().nth(1) {
-        if cmd.to_lowercase() == "benchmark"
-            || cmd.to_lowercase() == "--benchmark"
-            || cmd.to_lowercase() == "-benchmark"
-        {
-            println!("Running benchmark");
-            solver::solver::benchmark(env::args().nth(2).is_some());
+use soducant_core::solver::solver::{BenchmarkResult, SolveOptions};
+use soducant_core::sudoku::sudoku::{BOARD_SIZE, Board};
+use soducant_core::tuning::tuning::{TuningSpace, tune};
+use std::process::ExitCode;
+use std::time::{Duration, Instant};
+
+use crate::input::{read_multi_puzzle, read_puzzle};
+use crate::output::{
+    Format, Printer, Record, board_to_string, candidates_to_string, print_error, print_record,
+};
+
+/// The puzzle was read but has no (unique) answer, or didn't validate
+const EXIT_FAILURE: u8 = 1;
+/// The input couldn't be read or parsed
+const EXIT_BAD_INPUT: u8 = 2;
+
+#[derive(Parser)]
+#[command(
+    name = "soducant-cli",
+    version,
+    about = "Solve, generate and analyse sudokus",
+    after_help = "Puzzles are 81 characters, using 0 or . for empty cells, or the grid printed by --format grid. \
+                  Pass - or leave the puzzle out to read it from stdin.\n\n\
+                  Variant rules go on their own lines after the board: `cage <sum> r1c1 r1c2 ...` for a killer cage, \
+                  `diagonal` for Sudoku X, `regions <81 symbols>` for jigsaw regions (one symbol per cell, nine symbols in all), \
+                  `windoku` for the four extra windows of a hyper-sudoku, `extra r1c1 r1c2 ...` for any other nine cells holding every digit, \
+                  `anti-knight` and `anti-king` to forbid equal digits a knight's or king's move apart, \
+                  `thermo r1c1 r1c2 ...` for a thermometer from its bulb, `arrow r1c1 r1c2 ...` for an arrow from its circle, \
+                  `sandwich r3 15` or `sandwich c5 0` for the sum between the 1 and the 9 of a row or column, \
+                  `odd r1c1 ...` or `even r1c1 ...` for shaded cells, `greater r1c1 r1c2` when the first cell is larger, \
+                  and `white`, `black`, `x` or `v` followed by two neighbouring cells for Kropki dots and XV markers. \
+                  Add `negative kropki` or `negative xv` when every such marker is shown. \
+                  The board can be left out when every cell starts empty.\n\n\
+                  With --format json or csv, failures are reported in the \"error\" field of the result; \
+                  errors reading the input are printed to stderr in the same format.\n\n\
+                  Exit codes: 0 on success, 1 if the puzzle is unsolvable or invalid, 2 if the input couldn't be read."
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Solve a puzzle
+    Solve(SolveArgs),
+    /// Solve puzzles from a file or stdin, one per line
+    Batch(BatchArgs),
+    /// Generate new puzzles with a unique solution
+    Generate(GenerateArgs),
+    /// Rate how hard a puzzle is
+    Rate(PuzzleArgs),
+    /// Check that a puzzle is well-formed and has exactly one solution
+    Validate(PuzzleArgs),
+    /// Suggest the next digit to place
+    Hint(PuzzleArgs),
+    /// Show the candidates left in every empty cell, as pencil marks
+    Candidates(PuzzleArgs),
+    /// Solve with human techniques, listing every deduction
+    Explain(ExplainArgs),
+    /// Count the solutions of a puzzle
+    Count(CountArgs),
+    /// Solve the bundled test puzzles and report the pass rate
+    Benchmark(BenchmarkArgs),
+    /// Convert a puzzle between formats
+    Convert(ConvertArgs),
+    /// Search for annealing parameters with the best solve rate per unit time
+    Tune(TuneArgs),
+    /// Play a puzzle in the terminal
+    Play(PlayArgs),
+    /// Solve a samurai: five grids sharing their corner boxes with a centre grid
+    Samurai(PuzzleArgs),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Backend {
+    /// Simulated annealing
+    Anneal,
+    /// Backtracking search
+    Exact,
+}
+
+#[derive(Args)]
+struct PuzzleArgs {
+    /// Puzzle to read, or - for stdin
+    puzzle: Option<String>,
+    #[arg(long, value_enum, default_value_t = Format::Grid)]
+    format: Format,
+}
+
+#[derive(Args, Clone)]
+pub struct SolverArgs {
+    #[arg(long, value_enum, default_value_t = Backend::Anneal)]
+    backend: Backend,
+    /// Seed for the annealer
+    #[arg(long)]
+    seed: Option<u64>,
+    /// Keep retrying the annealer for up to this many seconds
+    #[arg(long, value_name = "SECONDS")]
+    time_limit: Option<f64>,
+}
+
+#[derive(Args)]
+struct SolveArgs {
+    #[command(flatten)]
+    puzzle: PuzzleArgs,
+    #[command(flatten)]
+    solver: SolverArgs,
+}
+
+#[derive(Args)]
+pub struct BatchArgs {
+    /// File with one puzzle per line, or - for stdin
+    input: Option<String>,
+    #[command(flatten)]
+    solver: SolverArgs,
+    /// Number of puzzles to solve in parallel
+    #[arg(long, short, default_value_t = 1)]
+    jobs: usize,
+    #[arg(long, value_enum, default_value_t = Format::Plain)]
+    format: Format,
+}
+
+#[derive(Args)]
+struct GenerateArgs {
+    /// Number of puzzles to generate
+    #[arg(long, default_value_t = 1)]
+    count: usize,
+    /// Stop removing clues once this many are left
+    #[arg(long, default_value_t = 0)]
+    min_clues: usize,
+    #[arg(long)]
+    seed: Option<u64>,
+    #[arg(long, value_enum, default_value_t = Format::Grid)]
+    format: Format,
+}
+
+#[derive(Args)]
+struct CountArgs {
+    #[command(flatten)]
+    puzzle: PuzzleArgs,
+    /// Stop counting after this many solutions
+    #[arg(long, default_value_t = 1000)]
+    limit: usize,
+}
+
+#[derive(Args)]
+struct BenchmarkArgs {
+    #[arg(long, value_enum, default_value_t = Backend::Anneal)]
+    backend: Backend,
+    /// Print every puzzle and its solution (grid format only)
+    #[arg(long)]
+    verbose: bool,
+    #[arg(long, value_enum, default_value_t = Format::Grid)]
+    format: Format,
+}
+
+#[derive(Args)]
+struct ExplainArgs {
+    #[command(flatten)]
+    puzzle: PuzzleArgs,
+    /// Techniques not to use, by their JSON names, e.g. x-wing,xy-wing
+    #[arg(long, value_delimiter = ',')]
+    skip: Vec<String>,
+    /// Longest chain to look for, in links
+    #[arg(long, default_value_t = LogicOptions::default().max_chain_length)]
+    max_chain: usize,
+    /// Also use techniques that need a single solution, such as unique rectangles. The
+    /// puzzle is checked first.
+    #[arg(long)]
+    unique: bool,
+}
+
+#[derive(Args)]
+struct ConvertArgs {
+    #[command(flatten)]
+    puzzle: PuzzleArgs,
+    /// Character used for empty cells in plain output
+    #[arg(long, default_value_t = '0')]
+    empty: char,
+}
+
+#[derive(Args)]
+struct TuneArgs {
+    /// Number of bundled puzzles to tune on
+    #[arg(long, default_value_t = 50)]
+    puzzles: usize,
+    /// Iteration budget per puzzle
+    #[arg(long, default_value_t = 20000)]
+    max_iter: i32,
+    /// Number of results to print
+    #[arg(long, default_value_t = 5)]
+    top: usize,
+    #[arg(long, value_enum, default_value_t = Format::Grid)]
+    format: Format,
+}
+
+#[derive(Args)]
+pub struct PlayArgs {
+    /// Puzzle to play. Without it, --file or --resume, a new one is generated
+    puzzle: Option<String>,
+    /// Read the puzzle from a file
+    #[arg(long, conflicts_with = "puzzle")]
+    file: Option<String>,
+    /// Continue a saved game
+    #[arg(long, conflicts_with_all = ["puzzle", "file"])]
+    resume: Option<String>,
+    /// Where `s` and quitting save the game, in JSON if it ends in .json. Defaults to the
+    /// --resume file.
+    #[arg(long)]
+    save: Option<String>,
+    /// Seed for the generator
+    #[arg(long)]
+    seed: Option<u64>,
+    /// Stop removing clues from generated puzzles once this many are left
+    #[arg(long, default_value_t = 0)]
+    min_clues: usize,
+}
+
+impl Command {
+    fn format(&self) -> Format {
+        match self {
+            Command::Solve(args) => args.puzzle.format,
+            Command::Batch(args) => args.format,
+            Command::Generate(args) => args.format,
+            Command::Rate(args)
+            | Command::Validate(args)
+            | Command::Hint(args)
+            | Command::Candidates(args)
+            | Command::Samurai(args) => args.format,
+            Command::Explain(args) => args.puzzle.format,
+            Command::Count(args) => args.puzzle.format,
+            Command::Benchmark(args) => args.format,
+            Command::Convert(args) => args.puzzle.format,
+            Command::Tune(args) => args.format,
+            Command::Play(_) => Format::Grid,
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let format = cli.command.format();
+
+    let result = match cli.command {
+        Command::Solve(args) => run_solve(args),
+        Command::Batch(args) => batch::run_batch(args),
+        Command::Generate(args) => run_generate(args),
+        Command::Rate(args) => run_rate(args),
+        Command::Validate(args) => run_validate(args),
+        Command::Hint(args) => run_hint(args),
+        Command::Candidates(args) => run_candidates(args),
+        Command::Explain(args) => run_explain(args),
+        Command::Count(args) => run_count(args),
+        Command::Benchmark(args) => run_benchmark(args),
+        Command::Convert(args) => run_convert(args),
+        Command::Tune(args) => run_tune(args),
+        Command::Play(args) => play::run_play(args),
+        Command::Samurai(args) => run_samurai(args),
+    };
+
+    return exit_code(format, result);
+}
+
+/// Errors that escape a command mean its input couldn't be used
+fn exit_code(format: Format, result: Result<ExitCode, Box<dyn std::error::Error>>) -> ExitCode {
+    match result {
+        Ok(code) => code,
+        Err(e) => {
+            print_error(format, &e.to_string());
+            ExitCode::from(EXIT_BAD_INPUT)
+        }
+    }
+}
+
+pub fn rng_from_seed(seed: Option<u64>) -> StdRng {
+    match seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_os_rng(),
+    }
+}
+
+/// Prints a command's result with its `error` field, which also goes to stderr in the
+/// human-readable formats
+fn report(
+    format: Format,
+    record: Record,
+    error: Option<String>,
+) -> Result<ExitCode, Box<dyn std::error::Error>> {
+    let record = record.field("error", error.clone());
+    print_record(format, &record)?;
+
+    let Some(message) = error else {
+        return Ok(ExitCode::SUCCESS);
+    };
+    if matches!(format, Format::Plain | Format::Grid) {
+        eprintln!("{}", message);
+    }
+    return Ok(ExitCode::from(EXIT_FAILURE));
+}
+
+/// Solves with the chosen backend, returning the full grid
+pub fn solve_board(board: &Board, args: &SolverArgs) -> Result<Board, Box<dyn std::error::Error>> {
+    match args.backend {
+        Backend::Exact => exact::solve(board),
+        Backend::Anneal => {
+            let options = SolveOptions {
+                seed: args.seed,
+                time_limit: args.time_limit.map(Duration::from_secs_f64),
+            };
+            let solution = solver::solver::solve_board_with_options(board, &options)?;
+            board.combine(&solution)
+        }
+    }
+}
+
+fn run_solve(args: SolveArgs) -> Result<ExitCode, Box<dyn std::error::Error>> {
+    let board = read_puzzle(args.puzzle.puzzle.as_deref())?;
+    let record = Record::new().field("puzzle", board.to_clean_string());
+    return match solve_board(&board, &args.solver) {
+        Ok(solved) => report(
+            args.puzzle.format,
+            record
+                .field("solution", solved.to_clean_string())
+                .plain(solved.to_clean_string())
+                .grid(board_to_string(&solved, Format::Grid, '0')),
+            None,
+        ),
+        Err(e) => report(
+            args.puzzle.format,
+            record.field("solution", Value::Null),
+            Some(format!("Couldn't solve sudoku: {}", e)),
+        ),
+    };
+}
+
+fn run_generate(args: GenerateArgs) -> Result<ExitCode, Box<dyn std::error::Error>> {
+    let mut rng = rng_from_seed(args.seed);
+    let mut printer = Printer::new(args.format, std::io::stdout().lock());
+    for _ in 0..args.count {
+        let board = generate(&mut rng, args.min_clues);
+        printer.print(
+            &Record::new()
+                .field("puzzle", board.to_clean_string())
+                .field("clues", board.clue_count())
+                .plain(board.to_clean_string())
+                .grid(board_to_string(&board, Format::Grid, '0')),
+        )?;
+    }
+    return Ok(ExitCode::SUCCESS);
+}
+
+fn run_rate(args: PuzzleArgs) -> Result<ExitCode, Box<dyn std::error::Error>> {
+    let board = read_puzzle(args.puzzle.as_deref())?;
+    let record = Record::new().field("puzzle", board.to_clean_string());
+    return match rate(&board) {
+        Ok(rating) => report(
+            args.format,
+            record
+                .field("difficulty", rating.difficulty.to_string())
+                .field("clues", rating.clues)
+                .field("guesses", rating.guesses)
+                .field("se_rating", rating.se)
+                .plain(rating.difficulty.to_string())
+                .grid(format!(
+                    "Difficulty: {} ({} clues, {} guesses, SE {})",
+                    rating.difficulty,
+                    rating.clues,
+                    rating.guesses,
+                    rating
+                        .se
+                        .map_or("unknown".to_string(), |se| format!("{:.1}", se))
+                )),
+            None,
+        ),
+        Err(e) => report(
+            args.format,
+            record
+                .field("difficulty", Value::Null)
+                .field("clues", board.clue_count())
+                .field("guesses", Value::Null)
+                .field("se_rating", Value::Null),
+            Some(format!("Couldn't rate sudoku: {}", e)),
+        ),
+    };
+}
+
+fn run_validate(args: PuzzleArgs) -> Result<ExitCode, Box<dyn std::error::Error>> {
+    let (puzzle, problem) = match read_puzzle(args.puzzle.as_deref()) {
+        Err(e) => (None, Some(e.to_string())),
+        Ok(board) => {
+            let problem = match exact::solve(&board) {
+                Err(e) => Some(e.to_string()),
+                Ok(_) if exact::count_solutions(&board, 2) > 1 => {
+                    Some("Puzzle has more than one solution".to_string())
+                }
+                Ok(_) => None,
+            };
+            (Some(board.to_clean_string()), problem)
+        }
+    };
+
+    let record = Record::new()
+        .field("puzzle", puzzle)
+        .field("valid", problem.is_none());
+    let record = match &problem {
+        None => record.plain("valid").grid("Puzzle is valid"),
+        Some(_) => record.plain("invalid").grid("Puzzle is invalid"),
+    };
+    return report(args.format, record, problem);
+}
+
+fn run_hint(args: PuzzleArgs) -> Result<ExitCode, Box<dyn std::error::Error>> {
+    let board = read_puzzle(args.puzzle.as_deref())?;
+    let record = Record::new().field("puzzle", board.to_clean_string());
+    let error = match hint(&board) {
+        Ok(Some(hint)) => {
+            let (row, col) = (hint.row + 1, hint.col + 1);
+            return report(
+                args.format,
+                record
+                    .field("row", row)
+                    .field("col", col)
+                    .field("digit", hint.digit)
+                    .plain(format!("r{}c{}={}", row, col, hint.digit))
+                    .grid(format!(
+                        "Place {} at row {}, column {}",
+                        hint.digit, row, col
+                    )),
+                None,
+            );
+        }
+        Ok(None) => "Board is already full".to_string(),
+        Err(e) => format!("Couldn't find a hint: {}", e),
+    };
+    let record = record
+        .field("row", Value::Null)
+        .field("col", Value::Null)
+        .field("digit", Value::Null);
+    return report(args.format, record, Some(error));
+}
+
+fn run_candidates(args: PuzzleArgs) -> Result<ExitCode, Box<dyn std::error::Error>> {
+    let board = read_puzzle(args.puzzle.as_deref())?;
+    let candidates = Candidates::from_board(&board);
+    let notes: Vec<String> = (0..BOARD_SIZE * BOARD_SIZE)
+        .map(|k| {
+            let (row, col) = (k / BOARD_SIZE, k % BOARD_SIZE);
+            return candidates.digits(row, col).map(|d| d.to_string()).collect();
+        })
+        .collect();
+    let record = Record::new()
+        .field("puzzle", board.to_clean_string())
+        .field("candidates", notes)
+        .plain(candidates_to_string(&board, &candidates, Format::Plain))
+        .grid(candidates_to_string(&board, &candidates, Format::Grid));
+    print_record(args.format, &record)?;
+    return Ok(ExitCode::SUCCESS);
+}
+
+fn run_explain(args: ExplainArgs) -> Result<ExitCode, Box<dyn std::error::Error>> {
+    let skip = args
+        .skip
+        .iter()
+        .map(|name| name.parse())
+        .collect::<Result<Vec<Technique>, _>>()?;
+    let options = LogicOptions {
+        techniques: Technique::ALL
+            .into_iter()
+            .filter(|technique| !skip.contains(technique))
+            .collect(),
+        max_chain_length: args.max_chain,
+        unique: args.unique,
+    };
+    let args = args.puzzle;
+    let board = read_puzzle(args.puzzle.as_deref())?;
+    let record = Record::new().field("puzzle", board.to_clean_string());
+    // Uniqueness techniques are only sound with exactly one solution
+    if options.unique {
+        let problem = match exact::count_solutions(&board, 2) {
+            0 => Some("Puzzle has no solution"),
+            1 => None,
+            _ => Some("Puzzle has more than one solution"),
+        };
+        if let Some(problem) = problem {
+            let record = record
+                .field("solved", false)
+                .field("steps", Value::Null)
+                .field("board", Value::Null);
+            return report(args.format, record, Some(problem.to_string()));
+        }
+    }
+    let trace = match solve_logically_with(&board, &options) {
+        Ok(trace) => trace,
+        Err(e) => {
+            let record = record
+                .field("solved", false)
+                .field("steps", Value::Null)
+                .field("board", Value::Null);
+            return report(
+                args.format,
+                record,
+                Some(format!("Puzzle is broken: {}", e)),
+            );
+        }
+    };
+
+    let lines: Vec<String> = trace.steps.iter().map(Step::to_string).collect();
+    let numbered: Vec<String> = lines
+        .iter()
+        .enumerate()
+        .map(|(k, line)| format!("{:>3}. {}", k + 1, line))
+        .collect();
+    let (end, error) = if trace.is_solved() {
+        (board_to_string(&trace.board, Format::Grid, '0'), None)
+    } else {
+        let error = format!("Stalled after {} steps: no technique applies", lines.len());
+        let grid = candidates_to_string(&trace.board, &trace.candidates, Format::Grid);
+        (grid, Some(error))
+    };
+    let record = record
+        .field("solved", trace.is_solved())
+        .field("steps", serde_json::to_value(&trace.steps)?)
+        .field("board", trace.board.to_clean_string())
+        .plain(
+            [lines, vec![trace.board.to_clean_string()]]
+                .concat()
+                .join("\n"),
+        )
+        .grid(format!("{}\n{}", numbered.join("\n"), end));
+    return report(args.format, record, error);
+}
+
+fn run_count(args: CountArgs) -> Result<ExitCode, Box<dyn std::error::Error>> {
+    let board = read_puzzle(args.puzzle.puzzle.as_deref())?;
+    let count = exact::count_solutions(&board, args.limit);
+    let limit_reached = count >= args.limit;
+    let record = Record::new()
+        .field("puzzle", board.to_clean_string())
+        .field("solutions", count)
+        .field("limit_reached", limit_reached)
+        .plain(count.to_string())
+        .grid(if limit_reached {
+            format!("At least {} solutions (stopped at limit)", count)
         } else {
-            if let Ok(board) = sudoku::sudoku::Board::from_string(&cmd) {
-                println!("Solving the following sudoku:\n{}", board);
-                if let Ok(solution) = solver::solver::solve(&cmd) {
-                    println!(
-                        "The solved board is:\n{}",
-                        Solution::from_board(Board::from_string(&solution).unwrap())
-                    );
-                } else {
-                    println!("Couldn't solve sudoku");
+            format!("{} solution(s)", count)
+        });
+    let error = (count == 0).then(|| "Puzzle has no solution".to_string());
+    return report(args.puzzle.format, record, error);
+}
+
+fn run_benchmark(args: BenchmarkArgs) -> Result<ExitCode, Box<dyn std::error::Error>> {
+    let verbose = args.verbose && args.format == Format::Grid;
+    if args.format == Format::Grid {
+        println!("Running benchmark");
+    }
+
+    let result = match args.backend {
+        Backend::Anneal => solver::solver::run_benchmark(|board, solution| {
+            if !verbose {
+                return;
+            }
+            println!("Now solving:\n{}", board);
+            match solution {
+                Some(solved) => println!("Solution found: \n{}", solved),
+                None => println!("Couldn't find solution - miss"),
+            }
+        }),
+        Backend::Exact => {
+            let mut passes = 0;
+            let mut attempts = 0;
+            let start = Instant::now();
+            for line in solver::solver::test_puzzles() {
+                let board = Board::from_string(line)?;
+                if verbose {
+                    println!("Now solving:\n{}", board);
+                }
+                match exact::solve(&board) {
+                    Ok(solved) => {
+                        passes += 1;
+                        if verbose {
+                            println!("Solution found: \n{}", solved);
+                        }
+                    }
+                    Err(_) if verbose => println!("Couldn't find solution - miss"),
+                    Err(_) => {}
                 }
-            } else {
-                println!("Couldn't read sudoku from input");
+                attempts += 1;
+            }
+            BenchmarkResult {
+                passes,
+                attempts,
+                duration: start.elapsed(),
             }
         }
-        return;
+    };
+
+    let backend = args.backend.to_possible_value().unwrap();
+    let record = Record::new()
+        .field("backend", backend.get_name())
+        .field("passes", result.passes)
+        .field("attempts", result.attempts)
+        .field("pass_rate", result.pass_rate())
+        .field("seconds", result.duration.as_secs_f64())
+        .plain(format!(
+            "{} {} {}",
+            result.passes,
+            result.attempts,
+            result.duration.as_secs_f64()
+        ))
+        .grid(format!(
+            "Benchmarking results: {}/{} ({}%) passes\nTime taken: {:?}",
+            result.passes,
+            result.attempts,
+            result.pass_rate(),
+            result.duration
+        ));
+    print_record(args.format, &record)?;
+    return Ok(ExitCode::SUCCESS);
+}
+
+fn run_convert(args: ConvertArgs) -> Result<ExitCode, Box<dyn std::error::Error>> {
+    let board = read_puzzle(args.puzzle.puzzle.as_deref())?;
+    let puzzle = board_to_string(&board, Format::Plain, args.empty);
+    // Rule lines follow the board so variants survive the round trip
+    let rules: String = rule_lines(&board)
+        .iter()
+        .map(|line| format!("\n{}", line))
+        .collect();
+    let record = Record::new()
+        .field("puzzle", puzzle.clone())
+        .field("clues", board.clue_count())
+        .field("rules", rules.trim_start())
+        .plain(puzzle + &rules)
+        .grid(board_to_string(&board, Format::Grid, '0') + &rules);
+    print_record(args.puzzle.format, &record)?;
+    return Ok(ExitCode::SUCCESS);
+}
+
+fn run_samurai(args: PuzzleArgs) -> Result<ExitCode, Box<dyn std::error::Error>> {
+    let board = read_multi_puzzle(args.puzzle.as_deref())?;
+    let record = Record::new().field("puzzle", board.to_layout_string());
+    return match multigrid::solve(&board) {
+        Ok(solved) => report(
+            args.format,
+            record
+                .field("solution", solved.to_layout_string())
+                .plain(solved.to_layout_string().trim_end())
+                .grid(solved.to_string().trim_end()),
+            None,
+        ),
+        Err(e) => report(
+            args.format,
+            record.field("solution", Value::Null),
+            Some(format!("Couldn't solve samurai: {}", e)),
+        ),
+    };
+}
+
+fn run_tune(args: TuneArgs) -> Result<ExitCode, Box<dyn std::error::Error>> {
+    let puzzles: Vec<&str> = solver::solver::test_puzzles().take(args.puzzles).collect();
+    let space = TuningSpace::default();
+    if args.format == Format::Grid {
+        println!(
+            "Tuning {} parameter sets on {} puzzles ({} iterations each)",
+            space.candidates().len(),
+            puzzles.len(),
+            args.max_iter
+        );
     }
+    let results = tune(&puzzles, &space, args.max_iter);
 
-    println!(
-        "No CLI input found, using default board and solving (pass a board by string or use --benchmark to use other modes)"
-    );
-    let board = Board::from_string(
-        "278000401609100050005006900430809000706003000091000800000020173860001004107934685",
-    )
-    .unwrap();
+    let mut printer = Printer::new(args.format, std::io::stdout().lock());
+    for result in results.iter().take(args.top) {
+        let params = &result.params;
+        printer.print(
+            &Record::new()
+                .field("temperature", params.temperature)
+                .field("decay", params.decay)
+                .field("reheat_threshold", params.reheat_threshold)
+                .field("reheat_strength", params.reheat_strength)
+                .field("solved", result.solved)
+                .field("attempts", result.attempts)
+                .field("seconds", result.duration.as_secs_f64())
+                .field("solves_per_second", result.solves_per_second())
+                .plain(format!(
+                    "{} {} {} {} {} {} {} {}",
+                    params.temperature,
+                    params.decay,
+                    params.reheat_threshold,
+                    params.reheat_strength,
+                    result.solved,
+                    result.attempts,
+                    result.duration.as_secs_f64(),
+                    result.solves_per_second()
+                ))
+                .grid(result.to_string()),
+        )?;
+    }
+    printer.flush()?;
+    if let (Some(best), Format::Grid) = (results.first(), args.format) {
+        println!("Best parameters: {:?}", best.params);
+    }
+    return Ok(ExitCode::SUCCESS);
+}
 
-    println!("Board:\n{}", board);
+#[cfg(test)]
+mod tests {
+    use std::process::ExitCode;
 
-    println!("Solving sudoku");
+    use crate::output::{Format, Record};
+    use crate::{EXIT_BAD_INPUT, EXIT_FAILURE, exit_code, report};
 
-    let mut solver = Solver::default();
-    let solution = solver.solve(10000);
+    #[test]
+    fn exit_codes() {
+        let code = report(Format::Json, Record::new(), None).unwrap();
+        assert_eq!(code, ExitCode::SUCCESS);
+        let code = report(Format::Json, Record::new(), Some("no solution".into())).unwrap();
+        assert_eq!(code, ExitCode::from(EXIT_FAILURE));
 
-    if let Some(solved) = solution {
-        println!("Solution:\n{}", solved);
-    } else {
-        println!(
-            "Couldn't solve sudoku. Ended with score {} and board:\n{}",
-            solver.current_score, solver.solution
-        )
+        assert_eq!(
+            exit_code(Format::Json, Ok(ExitCode::from(EXIT_FAILURE))),
+            ExitCode::from(EXIT_FAILURE)
+        );
+        assert_eq!(
+            exit_code(Format::Json, Err(Box::from("Puzzle is too short"))),
+            ExitCode::from(EXIT_BAD_INPUT)
+        );
     }
 }
@@ -0,0 +1,244 @@
+use clap::ValueEnum;
+use serde_json::{Map, Value};
+use soducant_core::constraint::constraint::Candidates;
+use soducant_core::sudoku::sudoku::{BOARD_SIZE, Board};
+
+use std::io::Write;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// Bare values, boards as single lines of 81 characters
+    Plain,
+    /// Human-readable text with box-drawing grids
+    Grid,
+    /// One JSON object per line
+    Json,
+    /// Header row, then one row per result
+    Csv,
+}
+
+pub fn board_to_string(board: &Board, format: Format, empty: char) -> String {
+    match format {
+        Format::Grid => board.to_string().trim_end().to_string(),
+        _ => board
+            .to_clean_string()
+            .chars()
+            .map(|c| if c == '0' { empty } else { c })
+            .collect(),
+    }
+}
+
+/// Pencil marks laid out like the candidate grids of other sudoku tools: each cell shows its
+/// digit, or its candidates run together, with `-` for an empty cell that has none left. Plain
+/// output is one line per row; grid output pads the columns and draws the boxes.
+pub fn candidates_to_string(board: &Board, candidates: &Candidates, format: Format) -> String {
+    let cells: Vec<Vec<String>> = (0..BOARD_SIZE)
+        .map(|i| {
+            (0..BOARD_SIZE)
+                .map(|j| match board.get(i, j) {
+                    Some(num) => num.to_string(),
+                    None if candidates.count(i, j) == 0 => "-".to_string(),
+                    None => candidates.digits(i, j).map(|d| d.to_string()).collect(),
+                })
+                .collect()
+        })
+        .collect();
+    if format != Format::Grid {
+        return cells
+            .iter()
+            .map(|row| row.join(" "))
+            .collect::<Vec<_>>()
+            .join("\n");
+    }
+
+    // Jigsaw regions don't line up with the box separators, so those are left out
+    let boxes = board.regions().is_boxes();
+    let widths: Vec<usize> = (0..BOARD_SIZE)
+        .map(|j| cells.iter().map(|row| row[j].len()).max().unwrap_or(1))
+        .collect();
+    let lines: Vec<String> = cells
+        .iter()
+        .map(|row| {
+            let mut line = String::new();
+            for (j, cell) in row.iter().enumerate() {
+                if boxes && (j == 3 || j == 6) {
+                    line += " ⁞";
+                }
+                line += &format!(" {:<width$}", cell, width = widths[j]);
+            }
+            return line + " ";
+        })
+        .collect();
+    let width = lines[0].chars().count();
+    let separator: String = lines[0]
+        .chars()
+        .map(|c| if c == '⁞' { '⸭' } else { '⋯' })
+        .collect();
+
+    let mut out = format!("╔{}╗\n", "═".repeat(width));
+    for (i, line) in lines.iter().enumerate() {
+        if boxes && (i == 3 || i == 6) {
+            out += &format!("║{}║\n", separator);
+        }
+        out += &format!("║{}║\n", line);
+    }
+    out += &format!("╚{}╝", "═".repeat(width));
+    return out;
+}
+
+/// One result of a command. JSON and CSV output use the fields, in order; plain and grid
+/// output use the prepared text.
+#[derive(Default)]
+pub struct Record {
+    fields: Vec<(&'static str, Value)>,
+    plain: Option<String>,
+    grid: Option<String>,
+}
+
+impl Record {
+    pub fn new() -> Self {
+        return Self::default();
+    }
+
+    pub fn field(mut self, name: &'static str, value: impl Into<Value>) -> Self {
+        self.fields.push((name, value.into()));
+        return self;
+    }
+
+    /// Text printed in plain format. Without it nothing is printed.
+    pub fn plain(mut self, text: impl Into<String>) -> Self {
+        self.plain = Some(text.into());
+        return self;
+    }
+
+    /// Text printed in grid format. Without it nothing is printed.
+    pub fn grid(mut self, text: impl Into<String>) -> Self {
+        self.grid = Some(text.into());
+        return self;
+    }
+}
+
+fn csv_escape(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        return format!("\"{}\"", value.replace('"', "\"\""));
+    }
+    return value.to_string();
+}
+
+fn csv_value(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => csv_escape(s),
+        other => csv_escape(&other.to_string()),
+    }
+}
+
+/// Writes records in one format, emitting the CSV header before the first record
+pub struct Printer<W: Write> {
+    format: Format,
+    out: W,
+    header_written: bool,
+}
+
+impl<W: Write> Printer<W> {
+    pub fn new(format: Format, out: W) -> Self {
+        Printer {
+            format,
+            out,
+            header_written: false,
+        }
+    }
+
+    pub fn print(&mut self, record: &Record) -> std::io::Result<()> {
+        match self.format {
+            Format::Plain => {
+                if let Some(text) = &record.plain {
+                    writeln!(self.out, "{}", text)?;
+                }
+            }
+            Format::Grid => {
+                if let Some(text) = &record.grid {
+                    writeln!(self.out, "{}", text)?;
+                }
+            }
+            Format::Json => {
+                let object: Map<String, Value> = record
+                    .fields
+                    .iter()
+                    .map(|(name, value)| (name.to_string(), value.clone()))
+                    .collect();
+                writeln!(self.out, "{}", Value::Object(object))?;
+            }
+            Format::Csv => {
+                if !self.header_written {
+                    let header: Vec<&str> = record.fields.iter().map(|(name, _)| *name).collect();
+                    writeln!(self.out, "{}", header.join(","))?;
+                    self.header_written = true;
+                }
+                let row: Vec<String> = record.fields.iter().map(|(_, v)| csv_value(v)).collect();
+                writeln!(self.out, "{}", row.join(","))?;
+            }
+        }
+        return Ok(());
+    }
+
+    pub fn flush(&mut self) -> std::io::Result<()> {
+        return self.out.flush();
+    }
+}
+
+/// Prints a single record to stdout
+pub fn print_record(format: Format, record: &Record) -> std::io::Result<()> {
+    return Printer::new(format, std::io::stdout().lock()).print(record);
+}
+
+/// Reports an error on stderr in the requested format
+pub fn print_error(format: Format, message: &str) {
+    let record = Record::new()
+        .field("error", message)
+        .plain(format!("error: {}", message))
+        .grid(format!("error: {}", message));
+    let _ = Printer::new(format, std::io::stderr().lock()).print(&record);
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use super::{Format, Printer, Record};
+
+    fn printed(format: Format, records: &[Record]) -> String {
+        let mut out = Vec::new();
+        let mut printer = Printer::new(format, &mut out);
+        for record in records {
+            printer.print(record).unwrap();
+        }
+        return String::from_utf8(out).unwrap();
+    }
+
+    #[test]
+    fn prints_csv_header_once() {
+        let records = [
+            Record::new().field("line", 1).field("error", Value::Null),
+            Record::new().field("line", 2).field("error", "no solution"),
+        ];
+        assert_eq!(
+            printed(Format::Csv, &records),
+            "line,error\n1,\n2,no solution\n"
+        );
+        assert_eq!(printed(Format::Csv, &[]), "");
+    }
+
+    #[test]
+    fn escapes_csv_values() {
+        let record = Record::new()
+            .field("comma", "a,b")
+            .field("quote", "say \"hi\"")
+            .field("newline", "one\ntwo")
+            .field("plain", "r1c1");
+        assert_eq!(
+            printed(Format::Csv, &[record]),
+            "comma,quote,newline,plain\n\"a,b\",\"say \"\"hi\"\"\",\"one\ntwo\",r1c1\n"
+        );
+    }
+}
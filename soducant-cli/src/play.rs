@@ -0,0 +1,368 @@
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::style::{
+    Attribute, Color, Print, SetAttribute, SetBackgroundColor, SetForegroundColor,
+};
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{execute, queue};
+use soducant_core::generator::generator::generate;
+use soducant_core::save::save;
+use soducant_core::session::session::GameSession;
+use soducant_core::sudoku::sudoku::{BOARD_SIZE, Board};
+
+use std::io::Write;
+use std::process::ExitCode;
+use std::time::{Duration, Instant};
+
+use crate::input::parse_puzzle;
+use crate::{PlayArgs, rng_from_seed};
+
+const HELP: &str =
+    "arrows/hjkl move  1-9 enter  0/del clear  n notes  u undo  r redo  ? hint  s save  q quit";
+
+/// Terminal-side state around a `GameSession`
+struct Game {
+    session: GameSession,
+    cursor: (usize, usize),
+    pencil: bool,
+    message: String,
+    last_tick: Instant,
+    save_path: Option<String>,
+}
+
+impl Game {
+    fn new(session: GameSession, save_path: Option<String>) -> Self {
+        Game {
+            session,
+            cursor: (0, 0),
+            pencil: false,
+            message: String::new(),
+            last_tick: Instant::now(),
+            save_path,
+        }
+    }
+
+    fn tick(&mut self) {
+        let now = Instant::now();
+        self.session.tick(now - self.last_tick);
+        self.last_tick = now;
+    }
+
+    fn report(&mut self, result: Result<(), Box<dyn std::error::Error>>) {
+        match result {
+            Ok(()) if self.session.is_solved() => {
+                self.message = format!(
+                    "Solved in {} with {} mistake(s)!",
+                    format_duration(self.session.elapsed()),
+                    self.session.mistakes()
+                );
+            }
+            Ok(()) => {}
+            Err(e) => self.message = e.to_string(),
+        }
+    }
+
+    fn input_digit(&mut self, digit: i8) {
+        let (row, col) = self.cursor;
+        let result = if self.pencil {
+            self.session.toggle_note(row, col, digit)
+        } else if self
+            .session
+            .cell(row, col)
+            .is_ok_and(|cell| cell.value == Some(digit))
+        {
+            self.session.set_value(row, col, None)
+        } else {
+            self.session.set_value(row, col, Some(digit))
+        };
+        self.report(result);
+    }
+
+    fn clear(&mut self) {
+        let (row, col) = self.cursor;
+        let result = self.session.clear(row, col);
+        self.report(result);
+    }
+
+    fn undo(&mut self) {
+        match self.session.undo() {
+            Some(cell) => self.cursor = cell,
+            None => self.message = "Nothing to undo".to_string(),
+        }
+    }
+
+    fn redo(&mut self) {
+        match self.session.redo() {
+            Some(cell) => {
+                self.cursor = cell;
+                self.report(Ok(()));
+            }
+            None => self.message = "Nothing to redo".to_string(),
+        }
+    }
+
+    fn hint(&mut self) {
+        match self.session.hint() {
+            Ok(Some(hint)) => {
+                self.cursor = (hint.row, hint.col);
+                let result = self.session.set_value(hint.row, hint.col, Some(hint.digit));
+                self.message = format!("Hint: {} goes here", hint.digit);
+                self.report(result);
+            }
+            Ok(None) => self.message = "The board is full".to_string(),
+            Err(_) => self.message = "No hint: some of your entries are wrong".to_string(),
+        }
+    }
+
+    fn save(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(path) = &self.save_path else {
+            return Err(Box::from("No save file given, start with --save PATH"));
+        };
+        let text = if path.ends_with(".json") {
+            save::to_json(&self.session)
+        } else {
+            save::to_text(&self.session)
+        };
+        std::fs::write(path, text + "\n").map_err(|e| format!("Couldn't write {}: {}", path, e))?;
+        self.message = format!("Saved to {}", path);
+        return Ok(());
+    }
+
+    fn move_cursor(&mut self, d_row: isize, d_col: isize) {
+        let wrap = |v: usize, d: isize| (v as isize + d).rem_euclid(BOARD_SIZE as isize) as usize;
+        self.cursor = (wrap(self.cursor.0, d_row), wrap(self.cursor.1, d_col));
+    }
+
+    /// Returns false when the player quits
+    fn handle_key(&mut self, key: KeyEvent) -> bool {
+        self.message.clear();
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            KeyCode::Char('c') if ctrl => return false,
+            KeyCode::Char('z') if ctrl => self.undo(),
+            KeyCode::Char('y') if ctrl => self.redo(),
+            KeyCode::Up | KeyCode::Char('k') => self.move_cursor(-1, 0),
+            KeyCode::Down | KeyCode::Char('j') => self.move_cursor(1, 0),
+            KeyCode::Left | KeyCode::Char('h') => self.move_cursor(0, -1),
+            KeyCode::Right | KeyCode::Char('l') => self.move_cursor(0, 1),
+            KeyCode::Char(c @ '1'..='9') => self.input_digit(c as i8 - b'0' as i8),
+            KeyCode::Char('0') | KeyCode::Char('.') | KeyCode::Backspace | KeyCode::Delete => {
+                self.clear()
+            }
+            KeyCode::Char('n') => self.pencil = !self.pencil,
+            KeyCode::Char('u') => self.undo(),
+            KeyCode::Char('r') => self.redo(),
+            KeyCode::Char('?') => self.hint(),
+            KeyCode::Char('s') => {
+                if let Err(e) = self.save() {
+                    self.message = e.to_string();
+                }
+            }
+            _ => {}
+        }
+        return true;
+    }
+}
+
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    return format!("{:02}:{:02}", secs / 60, secs % 60);
+}
+
+/// Session errors only come from cells off the board, which drawing never asks for
+fn on_board<T>(result: Result<T, Box<dyn std::error::Error>>) -> std::io::Result<T> {
+    return result.map_err(|e| std::io::Error::other(e.to_string()));
+}
+
+/// Draws the board with the same frame as `Board`'s `Display`
+fn draw(out: &mut impl Write, game: &Game) -> std::io::Result<()> {
+    let session = &game.session;
+    let conflicts = session.conflicts();
+    // Jigsaw boards get region borders between cells instead of the box separators
+    let regions = session.givens().regions();
+    let boxes = regions.is_boxes();
+    let width = if boxes { 23 } else { 19 };
+    queue!(out, Clear(ClearType::All), MoveTo(0, 0))?;
+    queue!(out, Print(format!("╔{}╗\r\n", "═".repeat(width))))?;
+    for (row, conflicting) in conflicts.iter().enumerate() {
+        if boxes && (row == 3 || row == 6) {
+            queue!(out, Print("║⋯⋯⋯⋯⋯⋯⋯⸭⋯⋯⋯⋯⋯⋯⋯⸭⋯⋯⋯⋯⋯⋯⋯║\r\n"))?;
+        }
+        if !boxes && row > 0 {
+            let border =
+                |col: usize| regions.region_of(row - 1, col) != regions.region_of(row, col);
+            let line = |on: bool| if on { '─' } else { ' ' };
+            let mut text = String::from("║");
+            for col in 0..BOARD_SIZE {
+                text.push(line(border(col) || (col > 0 && border(col - 1))));
+                text.push(line(border(col)));
+            }
+            text.push(line(border(BOARD_SIZE - 1)));
+            queue!(out, Print(text + "║\r\n"))?;
+        }
+        queue!(out, Print("║"))?;
+        for (col, &conflict) in conflicting.iter().enumerate() {
+            if boxes && (col == 3 || col == 6) {
+                queue!(out, Print(" ⁞"))?;
+            }
+            let wall = col > 0 && regions.region_of(row, col - 1) != regions.region_of(row, col);
+            queue!(out, Print(if !boxes && wall { "│" } else { " " }))?;
+
+            let cell = on_board(session.cell(row, col))?;
+            let text = match on_board(session.value(row, col))? {
+                Some(num) => num.to_string(),
+                None if cell.notes != 0 => "·".to_string(),
+                None => " ".to_string(),
+            };
+            if on_board(session.is_given(row, col))? {
+                queue!(out, SetAttribute(Attribute::Bold))?;
+            } else {
+                queue!(out, SetForegroundColor(Color::Cyan))?;
+            }
+            if conflict {
+                queue!(out, SetBackgroundColor(Color::DarkRed))?;
+            }
+            if game.cursor == (row, col) {
+                queue!(out, SetAttribute(Attribute::Reverse))?;
+            }
+            queue!(out, Print(text), SetAttribute(Attribute::Reset))?;
+        }
+        queue!(out, Print(" ║\r\n"))?;
+    }
+    queue!(out, Print(format!("╚{}╝\r\n\r\n", "═".repeat(width))))?;
+
+    let (row, col) = game.cursor;
+    let cell = on_board(session.cell(row, col))?;
+    let notes: Vec<String> = (1..=9)
+        .filter(|d| cell.has_note(*d))
+        .map(|d| d.to_string())
+        .collect();
+    queue!(
+        out,
+        Print(format!(
+            "Time {}  Mistakes {}  Row {} Col {}  Mode: {}  Notes: {}\r\n",
+            format_duration(session.elapsed()),
+            session.mistakes(),
+            row + 1,
+            col + 1,
+            if game.pencil { "notes" } else { "digits" },
+            notes.join(" ")
+        )),
+        Print(format!("{}\r\n\r\n{}\r\n", game.message, HELP))
+    )?;
+    return out.flush();
+}
+
+/// Restores the terminal even if the game loop errors out
+struct TerminalGuard;
+
+impl TerminalGuard {
+    fn enter() -> std::io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        execute!(std::io::stdout(), EnterAlternateScreen, Hide)?;
+        return Ok(TerminalGuard);
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = execute!(std::io::stdout(), Show, LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+fn load_puzzle(args: &PlayArgs) -> Result<Board, Box<dyn std::error::Error>> {
+    if let Some(puzzle) = &args.puzzle {
+        return parse_puzzle(puzzle);
+    }
+    if let Some(path) = &args.file {
+        let text =
+            std::fs::read_to_string(path).map_err(|e| format!("Couldn't read {}: {}", path, e))?;
+        return parse_puzzle(&text);
+    }
+    return Ok(generate(&mut rng_from_seed(args.seed), args.min_clues));
+}
+
+fn load_session(args: &PlayArgs) -> Result<GameSession, Box<dyn std::error::Error>> {
+    if let Some(path) = &args.resume {
+        let text =
+            std::fs::read_to_string(path).map_err(|e| format!("Couldn't read {}: {}", path, e))?;
+        return Ok(save::load(&text).map_err(|e| format!("Couldn't resume {}: {}", path, e))?);
+    }
+    let board = load_puzzle(args)?;
+    return Ok(GameSession::new(board).map_err(|e| format!("Puzzle can't be played: {}", e))?);
+}
+
+pub fn run_play(args: PlayArgs) -> Result<ExitCode, Box<dyn std::error::Error>> {
+    let session = load_session(&args)?;
+    let save_path = args.save.clone().or(args.resume.clone());
+
+    let mut game = Game::new(session, save_path);
+    let guard = TerminalGuard::enter()?;
+    let mut out = std::io::stdout();
+
+    loop {
+        game.tick();
+        draw(&mut out, &game)?;
+        // Wake up every second to keep the timer ticking
+        if !event::poll(Duration::from_secs(1))? {
+            continue;
+        }
+        if let Event::Key(key) = event::read()?
+            && key.kind == KeyEventKind::Press
+            && !game.handle_key(key)
+        {
+            break;
+        }
+    }
+
+    drop(guard);
+    if game.save_path.is_some() {
+        game.tick();
+        game.save()?;
+        println!("{}", game.message);
+    }
+    return Ok(ExitCode::SUCCESS);
+}
+
+#[cfg(test)]
+mod tests {
+    use soducant_core::session::session::GameSession;
+    use soducant_core::sudoku::sudoku::Board;
+
+    use super::{Game, draw};
+
+    fn drawn(board: Board) -> String {
+        let game = Game::new(GameSession::new(board).unwrap(), None);
+        let mut out = Vec::new();
+        draw(&mut out, &game).unwrap();
+        return String::from_utf8(out).unwrap();
+    }
+
+    #[test]
+    fn draws_box_separators_only_for_boxes() {
+        let board = Board::from_string(
+            "278000401609100050005006900430809000706003000091000800000020173860001004107934685",
+        )
+        .unwrap();
+        let text = drawn(board);
+        assert!(text.contains("╔═══════════════════════╗"));
+        assert!(text.contains('⁞'));
+
+        // The jigsaw from the exact solver's tests
+        let mut board = Board::from_string(
+            "020450709078061004001000402700092000000100000306245100050024800000039000007008020",
+        )
+        .unwrap();
+        board.set_regions(
+            "412223333411122233411122663441526633441555666445585996777785996777785999788888899"
+                .parse()
+                .unwrap(),
+        );
+        let text = drawn(board);
+        assert!(text.contains("╔═══════════════════╗"));
+        assert!(!text.contains('⁞'));
+        assert!(text.contains('│'));
+    }
+}
@@ -0,0 +1,81 @@
+//! `--format json` promises one record per line on stdout, with nothing else mixed in
+
+#![allow(clippy::needless_return)]
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use serde_json::Value;
+
+/// The first three rows are full, so the annealer can only swap in the other regions
+const PARTLY_FULL: &str =
+    "534678912672195348198342567800060003400803001700020006060000280000419005000080079";
+
+fn json_records(args: &[&str], stdin: &str) -> Vec<Value> {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_soducant-cli"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    return stdout
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap_or_else(|_| panic!("not JSON: {}", line)))
+        .collect();
+}
+
+#[test]
+fn solve_prints_one_record() {
+    let records = json_records(
+        &["solve", "--format", "json", "--seed", "1", PARTLY_FULL],
+        "",
+    );
+    assert_eq!(records.len(), 1);
+    assert!(records[0]["solution"].is_string());
+}
+
+#[test]
+fn batch_prints_a_record_per_puzzle() {
+    let input = format!("{}\nnot a puzzle\n{}\n", PARTLY_FULL, PARTLY_FULL);
+    let records = json_records(&["batch", "--format", "json", "--seed", "1", "-"], &input);
+    let lines: Vec<u64> = records
+        .iter()
+        .map(|r| r["line"].as_u64().unwrap())
+        .collect();
+    assert_eq!(lines, [1, 2, 3]);
+    assert!(records[1]["error"].is_string());
+}
+
+#[test]
+fn benchmark_prints_one_record() {
+    let records = json_records(&["benchmark", "--format", "json"], "");
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0]["attempts"], 500);
+}
+
+#[test]
+fn explain_reports_puzzles_without_one_solution() {
+    // Empty, so it has many solutions; then two 1s in the first row, so it has none
+    let empty = "0".repeat(81);
+    let broken = format!("11{}", "0".repeat(79));
+    for puzzle in [empty, broken] {
+        let output = Command::new(env!("CARGO_BIN_EXE_soducant-cli"))
+            .args(["explain", "--unique", "--format", "json", &puzzle])
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(1));
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        let record: Value = serde_json::from_str(stdout.trim()).unwrap();
+        assert_eq!(record["solved"], false);
+        assert!(record["error"].is_string());
+    }
+}
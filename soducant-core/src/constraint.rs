@@ -0,0 +1,338 @@
+pub mod constraint {
+    use std::fmt::Debug;
+
+    use crate::sudoku::sudoku::{BOARD_SIZE, Board};
+
+    /// Digits on a board, `None` for empty cells
+    pub type Grid = [[Option<i8>; BOARD_SIZE]; BOARD_SIZE];
+
+    pub const ALL_DIGITS: u16 = 0b1_1111_1111;
+
+    /// Digits each cell may still take, as bitmasks with bit `d - 1` for digit `d`. These are
+    /// also the pencil marks a frontend can show as automatic notes.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Candidates {
+        masks: [[u16; BOARD_SIZE]; BOARD_SIZE],
+    }
+
+    impl Candidates {
+        /// Every digit allowed everywhere
+        pub fn all() -> Self {
+            return Candidates {
+                masks: [[ALL_DIGITS; BOARD_SIZE]; BOARD_SIZE],
+            };
+        }
+
+        /// Digits the empty cells of a board may take under all of its constraints. Filled
+        /// cells have none.
+        pub fn from_board(board: &Board) -> Self {
+            let mut candidates = Candidates::all();
+            candidates.narrow(board, &board.grid);
+            return candidates;
+        }
+
+        /// Updates the candidates for a digit placed in a cell, keeping any eliminated by hand.
+        /// `board` may already hold the digit. After clearing a cell, start again with
+        /// [`Candidates::from_board`], since that can bring candidates back.
+        pub fn place(&mut self, board: &Board, row: usize, col: usize, digit: i8) {
+            if digit_bit(digit) == 0 {
+                return;
+            }
+            let mut grid = board.grid;
+            grid[row][col] = Some(digit);
+            self.narrow(board, &grid);
+        }
+
+        /// Applies the board's constraints until nothing changes, then clears filled cells
+        fn narrow(&mut self, board: &Board, grid: &Grid) {
+            loop {
+                let before = *self;
+                for constraint in board.constraints() {
+                    constraint.propagate(grid, self);
+                }
+                if *self == before {
+                    break;
+                }
+            }
+            for (i, row) in grid.iter().enumerate() {
+                for (j, cell) in row.iter().enumerate() {
+                    if cell.is_some() {
+                        self.masks[i][j] = 0;
+                    }
+                }
+            }
+        }
+
+        pub fn get(&self, row: usize, col: usize) -> u16 {
+            return self.masks[row][col];
+        }
+
+        pub fn set(&mut self, row: usize, col: usize, mask: u16) {
+            self.masks[row][col] = mask & ALL_DIGITS;
+        }
+
+        pub fn contains(&self, row: usize, col: usize, digit: i8) -> bool {
+            return self.masks[row][col] & digit_bit(digit) != 0;
+        }
+
+        /// Candidate digits of a cell, smallest first
+        pub fn digits(&self, row: usize, col: usize) -> impl Iterator<Item = i8> + use<> {
+            let mask = self.masks[row][col];
+            return (1..=BOARD_SIZE as i8).filter(move |&digit| mask & digit_bit(digit) != 0);
+        }
+
+        pub fn count(&self, row: usize, col: usize) -> u32 {
+            return self.masks[row][col].count_ones();
+        }
+
+        pub fn eliminate(&mut self, row: usize, col: usize, digit: i8) {
+            self.masks[row][col] &= !digit_bit(digit);
+        }
+
+        /// Puts back a digit, e.g. one eliminated by mistake. Nothing checks that the digit
+        /// still fits.
+        pub fn restore(&mut self, row: usize, col: usize, digit: i8) {
+            self.masks[row][col] |= digit_bit(digit);
+        }
+
+        /// Keeps only the digits in `mask`
+        pub fn retain(&mut self, row: usize, col: usize, mask: u16) {
+            self.masks[row][col] &= mask;
+        }
+    }
+
+    impl Default for Candidates {
+        fn default() -> Self {
+            return Self::all();
+        }
+    }
+
+    /// Bit of a digit in a candidate mask. Anything outside 1-9 has none, so candidate
+    /// methods ignore it.
+    pub fn digit_bit(digit: i8) -> u16 {
+        if !(1..=BOARD_SIZE as i8).contains(&digit) {
+            return 0;
+        }
+        return 1 << (digit - 1);
+    }
+
+    /// Digits from `lo` to `hi`, clipped to 1-9
+    pub fn digits_between(lo: i32, hi: i32) -> u16 {
+        let (lo, hi) = (lo.max(1), hi.min(BOARD_SIZE as i32));
+        if lo > hi {
+            return 0;
+        }
+        return ((1 << (hi - lo + 1)) - 1) << (lo - 1);
+    }
+
+    /// Smallest digit in a mask, 10 or more when it is empty
+    pub fn lowest_digit(mask: u16) -> i32 {
+        return mask.trailing_zeros() as i32 + 1;
+    }
+
+    /// Largest digit in a mask, 0 when it is empty
+    pub fn highest_digit(mask: u16) -> i32 {
+        return 16 - mask.leading_zeros() as i32;
+    }
+
+    /// Reads a cell written as `r1c2`, counting from 1
+    pub fn parse_cell(text: &str) -> Result<(usize, usize), String> {
+        let (row, col) = text
+            .strip_prefix('r')
+            .and_then(|rest| rest.split_once('c'))
+            .ok_or_else(|| format!("Cell should look like r1c2: {}", text))?;
+        let parse = |v: &str| match v.parse::<usize>() {
+            Ok(v @ 1..=BOARD_SIZE) => Ok(v - 1),
+            _ => Err(format!("Cell is off the board: {}", text)),
+        };
+        return Ok((parse(row)?, parse(col)?));
+    }
+
+    /// A rule broken by some cells
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Violation {
+        /// Which rule, e.g. "row 3" or "cage 15"
+        pub rule: String,
+        /// (row, col) of the cells involved, 0-based
+        pub cells: Vec<(usize, usize)>,
+    }
+
+    /// A rule the digits on a board have to follow. Both solvers work through this trait, so a
+    /// new variant only needs an implementation and [`Board::add_constraint`].
+    ///
+    /// [`Board::add_constraint`]: crate::sudoku::sudoku::Board::add_constraint
+    pub trait Constraint: Debug + Send + Sync {
+        /// Rules already broken by the filled cells. Empty cells must never count as a
+        /// violation, since the exact solver checks partial grids with this.
+        fn violations(&self, grid: &Grid) -> Vec<Violation>;
+
+        /// How far a full grid is from following the rule, 0 when it does. The annealer
+        /// minimises the sum over all constraints, so nearer misses should cost less.
+        fn penalty(&self, grid: &Grid) -> u32;
+
+        /// Removes candidates of empty cells that can't be part of any solution given the
+        /// filled cells and the other candidates. Must never remove a digit that could still
+        /// work; leaving too many in only costs search time.
+        fn propagate(&self, grid: &Grid, candidates: &mut Candidates);
+
+        fn is_violated(&self, grid: &Grid) -> bool {
+            return !self.violations(grid).is_empty();
+        }
+
+        /// Puzzle rule lines that add this constraint back, as read by
+        /// [`apply_rules`](crate::rules::rules::apply_rules). `None` for constraints that have
+        /// no text form.
+        fn rule(&self) -> Option<String> {
+            return None;
+        }
+    }
+
+    /// Nine cells that hold every digit once: a row, column, region or diagonal
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct House {
+        pub name: String,
+        pub cells: [(usize, usize); BOARD_SIZE],
+    }
+
+    impl House {
+        pub fn new(name: impl Into<String>, cells: [(usize, usize); BOARD_SIZE]) -> Self {
+            return House {
+                name: name.into(),
+                cells,
+            };
+        }
+
+        /// Digits placed in the house
+        pub fn used(&self, grid: &Grid) -> u16 {
+            return self
+                .cells
+                .iter()
+                .filter_map(|&(i, j)| grid[i][j])
+                .fold(0, |used, num| used | digit_bit(num));
+        }
+
+        /// Digits missing from a house of a full grid, the same as [`Constraint::penalty`]
+        /// without building a [`Grid`]. Anything but a digit from 1 to 9 counts as empty.
+        pub fn missing(&self, digits: &[[i8; BOARD_SIZE]; BOARD_SIZE]) -> u32 {
+            let used = self
+                .cells
+                .iter()
+                .map(|&(i, j)| digits[i][j])
+                .filter(|digit| (1..=BOARD_SIZE as i8).contains(digit))
+                .fold(0, |used, digit| used | digit_bit(digit));
+            return BOARD_SIZE as u32 - used.count_ones();
+        }
+
+        pub fn contains(&self, cell: (usize, usize)) -> bool {
+            return self.cells.contains(&cell);
+        }
+    }
+
+    impl Constraint for House {
+        fn violations(&self, grid: &Grid) -> Vec<Violation> {
+            let mut out = Vec::new();
+            for (a, &(i1, j1)) in self.cells.iter().enumerate() {
+                for &(i2, j2) in &self.cells[a + 1..] {
+                    if grid[i1][j1].is_some() && grid[i1][j1] == grid[i2][j2] {
+                        out.push(Violation {
+                            rule: self.name.clone(),
+                            cells: vec![(i1, j1), (i2, j2)],
+                        });
+                    }
+                }
+            }
+            return out;
+        }
+
+        /// Number of digits missing
+        fn penalty(&self, grid: &Grid) -> u32 {
+            return BOARD_SIZE as u32 - self.used(grid).count_ones();
+        }
+
+        fn propagate(&self, grid: &Grid, candidates: &mut Candidates) {
+            let used = self.used(grid);
+            for &(i, j) in &self.cells {
+                if grid[i][j].is_none() {
+                    candidates.retain(i, j, !used);
+                }
+            }
+        }
+
+        fn is_violated(&self, grid: &Grid) -> bool {
+            let mut seen = 0;
+            for &(i, j) in &self.cells {
+                if let Some(num) = grid[i][j] {
+                    if seen & digit_bit(num) != 0 {
+                        return true;
+                    }
+                    seen |= digit_bit(num);
+                }
+            }
+            return false;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::constraint::constraint::{Candidates, Constraint, House};
+    use crate::sudoku::sudoku::Board;
+
+    #[test]
+    fn houses_report_repeats() {
+        let board = Board::from_string(
+            "770000000000000000000000000000000000000000000000000000000000000000000000000000000",
+        )
+        .unwrap();
+        let row = House::new("row 1", std::array::from_fn(|j| (0, j)));
+
+        let violations = row.violations(&board.grid);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].cells, vec![(0, 0), (0, 1)]);
+        assert!(row.is_violated(&board.grid));
+        assert_eq!(row.penalty(&board.grid), 8);
+
+        let mut candidates = Candidates::all();
+        row.propagate(&board.grid, &mut candidates);
+        assert!(!candidates.contains(0, 5, 7));
+        assert!(candidates.contains(0, 5, 6));
+        assert!(candidates.contains(1, 5, 7));
+    }
+
+    #[test]
+    fn tracks_candidates() {
+        let mut board = Board::from_string(
+            "530070000600195000098000060800060003400803001700020006060000280000419005000080079",
+        )
+        .unwrap();
+        let mut candidates = Candidates::from_board(&board);
+        assert_eq!(candidates.digits(0, 2).collect::<Vec<_>>(), vec![1, 2, 4]);
+        assert_eq!(candidates.count(0, 0), 0);
+        assert_eq!(candidates.count(4, 4), 1);
+
+        candidates.eliminate(0, 2, 1);
+        assert!(!candidates.contains(0, 2, 1));
+        board.set(0, 2, Some(4));
+        candidates.place(&board, 0, 2, 4);
+        assert_eq!(candidates.count(0, 2), 0);
+        assert!(!candidates.contains(0, 3, 4));
+        assert!(!candidates.contains(2, 0, 4));
+        assert!(candidates.contains(0, 3, 2));
+
+        candidates.restore(1, 1, 4);
+        assert!(candidates.contains(1, 1, 4));
+        assert_eq!(Candidates::from_board(&Board::default()), Candidates::all());
+
+        // Digits outside 1-9 from a frontend change nothing
+        let before = candidates;
+        for digit in [0, -1, 10, 17, i8::MIN, i8::MAX] {
+            assert!(!candidates.contains(0, 3, digit));
+            candidates.eliminate(0, 3, digit);
+            candidates.restore(0, 3, digit);
+            candidates.place(&board, 0, 3, digit);
+            board.set(0, 3, Some(digit));
+            assert_eq!(board.get(0, 3), None);
+        }
+        assert_eq!(candidates, before);
+    }
+}
@@ -0,0 +1,472 @@
+pub mod exact {
+    use rand::RngCore;
+    use rand::seq::SliceRandom;
+
+    use crate::constraint::constraint::{
+        ALL_DIGITS, Candidates, Constraint, Grid, House, digit_bit,
+    };
+    use crate::sudoku::sudoku::{BOARD_SIZE, Board};
+
+    const CELLS: usize = BOARD_SIZE * BOARD_SIZE;
+
+    /// How much work a backtracking search needed
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+    pub struct SearchStats {
+        /// Cells filled in, including ones later undone
+        pub nodes: u64,
+        /// Branches taken on cells with more than one candidate
+        pub guesses: u64,
+    }
+
+    struct Search<'a> {
+        grid: [u8; CELLS],
+        /// Same digits as `grid`, laid out the way constraints read them
+        cells: Grid,
+        /// Digits used in each house of the board
+        house_masks: Vec<u16>,
+        /// Houses each cell belongs to
+        houses_of: Vec<Vec<usize>>,
+        houses: &'a [House],
+        /// Constraints other than houses, checked through the `Constraint` trait
+        rules: Vec<&'a dyn Constraint>,
+        stats: SearchStats,
+        rng: Option<&'a mut dyn RngCore>,
+    }
+
+    impl<'a> Search<'a> {
+        fn new(board: &'a Board) -> Result<Self, Box<dyn std::error::Error>> {
+            let mut search = Search {
+                grid: [0; CELLS],
+                cells: [[None; BOARD_SIZE]; BOARD_SIZE],
+                house_masks: vec![0; board.houses().len()],
+                houses_of: vec![Vec::new(); CELLS],
+                houses: board.houses(),
+                rules: board.variant_constraints().collect(),
+                stats: SearchStats::default(),
+                rng: None,
+            };
+
+            for (i, house) in board.houses().iter().enumerate() {
+                for &(row, col) in &house.cells {
+                    search.houses_of[row * BOARD_SIZE + col].push(i);
+                }
+            }
+
+            for cell in 0..CELLS {
+                if let Some(num) = board.get(cell / BOARD_SIZE, cell % BOARD_SIZE) {
+                    if !(1..=9).contains(&num) {
+                        return Err(Box::from(format!(
+                            "{} at ({},{}) is not a digit from 1 to 9",
+                            num,
+                            cell / BOARD_SIZE,
+                            cell % BOARD_SIZE
+                        )));
+                    }
+                    if search.candidates(cell) & (1 << (num - 1)) == 0 {
+                        return Err(Box::from(format!(
+                            "{} at ({},{}) conflicts with another given",
+                            num,
+                            cell / BOARD_SIZE,
+                            cell % BOARD_SIZE
+                        )));
+                    }
+                    search.place(cell, num as u8);
+                }
+            }
+            for rule in &search.rules {
+                if let Some(violation) = rule.violations(&search.cells).first() {
+                    return Err(Box::from(format!(
+                        "Givens break the {} rule",
+                        violation.rule
+                    )));
+                }
+            }
+
+            return Ok(search);
+        }
+
+        /// Digits not yet used in any house of the cell
+        fn candidates(&self, cell: usize) -> u16 {
+            let used = self.houses_of[cell]
+                .iter()
+                .fold(0, |used, &house| used | self.house_masks[house]);
+            return !used & ALL_DIGITS;
+        }
+
+        fn place(&mut self, cell: usize, digit: u8) {
+            let bit = 1 << (digit - 1);
+            self.grid[cell] = digit;
+            self.cells[cell / BOARD_SIZE][cell % BOARD_SIZE] = Some(digit as i8);
+            for &house in &self.houses_of[cell] {
+                self.house_masks[house] |= bit;
+            }
+        }
+
+        fn unplace(&mut self, cell: usize) {
+            let bit = !(1 << (self.grid[cell] - 1));
+            self.grid[cell] = 0;
+            self.cells[cell / BOARD_SIZE][cell % BOARD_SIZE] = None;
+            for &house in &self.houses_of[cell] {
+                self.house_masks[house] &= bit;
+            }
+        }
+
+        fn breaks_rule(&self) -> bool {
+            return self.rules.iter().any(|rule| rule.is_violated(&self.cells));
+        }
+
+        /// Empty cell with the fewest candidates, or `None` once the grid is full
+        fn most_constrained(&self) -> Option<(usize, u16)> {
+            if !self.rules.is_empty() {
+                return self.most_constrained_with_rules();
+            }
+            let mut best: Option<(usize, u16)> = None;
+            for cell in 0..CELLS {
+                if self.grid[cell] != 0 {
+                    continue;
+                }
+                let candidates = self.candidates(cell);
+                if best.is_none_or(|(_, b)| candidates.count_ones() < b.count_ones()) {
+                    best = Some((cell, candidates));
+                    if candidates.count_ones() <= 1 {
+                        break;
+                    }
+                }
+            }
+            return best;
+        }
+
+        /// Like [`Search::most_constrained`], letting every rule narrow the candidates first
+        fn most_constrained_with_rules(&self) -> Option<(usize, u16)> {
+            let mut candidates = Candidates::all();
+            for cell in 0..CELLS {
+                let (row, col) = (cell / BOARD_SIZE, cell % BOARD_SIZE);
+                match self.grid[cell] {
+                    0 => candidates.set(row, col, self.candidates(cell)),
+                    digit => candidates.set(row, col, 1 << (digit - 1)),
+                }
+            }
+            for rule in &self.rules {
+                rule.propagate(&self.cells, &mut candidates);
+            }
+
+            // Rules can take a digit's last place in a house without emptying any one cell, so
+            // look for digits with one place left (or none, a dead end)
+            for house in self.houses {
+                let placed = house.used(&self.cells);
+                for digit in (1..=BOARD_SIZE as i8).filter(|&d| placed & digit_bit(d) == 0) {
+                    let mut places = house
+                        .cells
+                        .iter()
+                        .filter(|&&(i, j)| candidates.contains(i, j, digit));
+                    match (places.next(), places.next()) {
+                        (None, _) => {
+                            let &(i, j) = house
+                                .cells
+                                .iter()
+                                .find(|&&(i, j)| self.cells[i][j].is_none())?;
+                            return Some((i * BOARD_SIZE + j, 0));
+                        }
+                        (Some(&(i, j)), None) => {
+                            return Some((i * BOARD_SIZE + j, digit_bit(digit)));
+                        }
+                        _ => {}
+                    }
+                }
+            }
+
+            let mut best: Option<(usize, u16)> = None;
+            for cell in 0..CELLS {
+                if self.grid[cell] != 0 {
+                    continue;
+                }
+                let mask = candidates.get(cell / BOARD_SIZE, cell % BOARD_SIZE);
+                if best.is_none_or(|(_, b)| mask.count_ones() < b.count_ones()) {
+                    best = Some((cell, mask));
+                    if mask.count_ones() <= 1 {
+                        break;
+                    }
+                }
+            }
+            return best;
+        }
+
+        /// Counts solutions up to `limit`, keeping the first one found in `first`
+        fn run(&mut self, limit: usize, found: &mut usize, first: &mut Option<[u8; CELLS]>) {
+            let Some((cell, candidates)) = self.most_constrained() else {
+                *found += 1;
+                if first.is_none() {
+                    *first = Some(self.grid);
+                }
+                return;
+            };
+
+            let mut digits: Vec<u8> = (1..=9u8)
+                .filter(|d| candidates & (1 << (d - 1)) != 0)
+                .collect();
+            if let Some(rng) = self.rng.as_mut() {
+                digits.shuffle(rng);
+            }
+            if digits.len() > 1 {
+                self.stats.guesses += 1;
+            }
+
+            for digit in digits {
+                self.stats.nodes += 1;
+                self.place(cell, digit);
+                if !self.breaks_rule() {
+                    self.run(limit, found, first);
+                }
+                self.unplace(cell);
+                if *found >= limit {
+                    return;
+                }
+            }
+        }
+
+        fn to_board(grid: &[u8; CELLS], puzzle: &Board) -> Board {
+            let mut board = puzzle.clone();
+            for (cell, &digit) in grid.iter().enumerate() {
+                board.set(cell / BOARD_SIZE, cell % BOARD_SIZE, Some(digit as i8));
+            }
+            return board;
+        }
+    }
+
+    /// Finds a full grid consistent with the givens by backtracking
+    pub fn solve(board: &Board) -> Result<Board, Box<dyn std::error::Error>> {
+        return solve_with_stats(board).map(|(solved, _)| solved);
+    }
+
+    pub fn solve_with_stats(
+        board: &Board,
+    ) -> Result<(Board, SearchStats), Box<dyn std::error::Error>> {
+        let mut search = Search::new(board)?;
+        let mut found = 0;
+        let mut first = None;
+        search.run(1, &mut found, &mut first);
+
+        let grid = first.ok_or("Puzzle has no solution")?;
+        return Ok((Search::to_board(&grid, board), search.stats));
+    }
+
+    /// Like [`solve`], but tries digits in random order so empty or sparse boards give varied grids
+    pub fn solve_random(
+        board: &Board,
+        rng: &mut dyn RngCore,
+    ) -> Result<Board, Box<dyn std::error::Error>> {
+        let mut search = Search::new(board)?;
+        search.rng = Some(rng);
+        let mut found = 0;
+        let mut first = None;
+        search.run(1, &mut found, &mut first);
+
+        let grid = first.ok_or("Puzzle has no solution")?;
+        return Ok(Search::to_board(&grid, board));
+    }
+
+    /// Counts solutions, stopping at `limit`. Boards with conflicting givens have none.
+    pub fn count_solutions(board: &Board, limit: usize) -> usize {
+        let Ok(mut search) = Search::new(board) else {
+            return 0;
+        };
+        let mut found = 0;
+        search.run(limit, &mut found, &mut None);
+        return found;
+    }
+
+    pub fn has_unique_solution(board: &Board) -> bool {
+        return count_solutions(board, 2) == 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    use crate::constraint::constraint::{Candidates, Constraint, Grid, Violation};
+    use crate::exact::exact::{count_solutions, solve, solve_random};
+    use crate::solver::solver::test_puzzles;
+    use crate::sudoku::sudoku::{Board, Cage, Solution};
+
+    const KILLER_CAGES: &str = "
+19 r1c1 r2c1 r1c2 r2c2
+25 r1c3 r2c3 r2c4 r3c4
+17 r1c4 r1c5 r1c6
+11 r1c7 r1c8 r1c9
+14 r2c5 r2c6 r3c5
+8 r2c7 r2c8
+17 r2c9 r3c9 r3c8
+14 r3c1 r4c1 r5c1
+6 r3c2 r4c2 r4c3
+5 r3c3
+15 r3c6 r3c7
+10 r4c4 r5c4
+6 r4c5 r5c5
+21 r4c6 r4c7 r5c7
+11 r4c8 r4c9 r5c8
+23 r5c2 r5c3 r6c2
+18 r5c6 r6c6 r6c5 r7c5
+18 r5c9 r6c9 r7c9 r8c9
+14 r6c1 r7c1
+11 r6c3 r7c3 r7c4
+4 r6c4
+18 r6c7 r7c7 r8c7 r7c8
+3 r6c8
+19 r7c2 r8c2 r8c1
+9 r7c6 r8c6
+24 r8c3 r8c4 r8c5 r9c4
+23 r8c8 r9c8 r9c7
+10 r9c1 r9c2 r9c3
+7 r9c5 r9c6
+5 r9c9
+";
+
+    #[test]
+    fn solves_test_puzzles() {
+        for line in test_puzzles().take(20) {
+            let board = Board::from_string(line).unwrap();
+            let solved = solve(&board).unwrap();
+            for i in 0..9 {
+                for j in 0..9 {
+                    if let Some(num) = board.get(i, j) {
+                        assert_eq!(solved.get(i, j), Some(num));
+                    }
+                }
+            }
+            let empty = Board::default();
+            let score = empty
+                .score_solution(&Solution::from_board(solved).unwrap())
+                .unwrap();
+            assert_eq!(score, 0);
+        }
+    }
+
+    #[test]
+    fn counts_solutions() {
+        let unique = Board::from_string(
+            "278000401609100050005006900430809000706003000091000800000020173860001004107934685",
+        )
+        .unwrap();
+        assert_eq!(count_solutions(&unique, 10), 1);
+
+        assert_eq!(count_solutions(&Board::default(), 5), 5);
+
+        let conflicting = Board::from_string(
+            "770000000000000000000000000000000000000000000000000000000000000000000000000000000",
+        )
+        .unwrap();
+        assert_eq!(count_solutions(&conflicting, 5), 0);
+    }
+
+    #[test]
+    fn solves_killer() {
+        let mut board = Board::from_string(
+            "008090060009000000000000020000800000700000009000000000000000000000070090000000000",
+        )
+        .unwrap();
+        for cage in Cage::parse_list(KILLER_CAGES).unwrap() {
+            board.add_cage(cage).unwrap();
+        }
+
+        assert_eq!(count_solutions(&board, 2), 1);
+        assert_eq!(
+            solve(&board).unwrap().to_clean_string(),
+            "278395461649182357315746928432859716786213549591467832954628173863571294127934685"
+        );
+    }
+
+    #[test]
+    fn solves_diagonal() {
+        let mut board = Board::from_string(
+            "000406080006709100709120006000001067000508094840000530208010075300800000000002000",
+        )
+        .unwrap();
+        assert!(count_solutions(&board, 2) > 1);
+
+        board.set_diagonal(true);
+        assert_eq!(count_solutions(&board, 2), 1);
+        let solved = solve(&board).unwrap();
+        for anti in [false, true] {
+            let mut seen: Vec<i8> = (0..9)
+                .map(|i| solved.get(i, if anti { 8 - i } else { i }).unwrap())
+                .collect();
+            seen.sort();
+            assert_eq!(seen, (1..=9).collect::<Vec<i8>>());
+        }
+    }
+
+    #[test]
+    fn solves_jigsaw() {
+        let mut board = Board::from_string(
+            "020450709078061004001000402700092000000100000306245100050024800000039000007008020",
+        )
+        .unwrap();
+        board.set_regions(
+            "412223333411122233411122663441526633441555666445585996777785996777785999788888899"
+                .parse()
+                .unwrap(),
+        );
+
+        assert_eq!(count_solutions(&board, 2), 1);
+        assert_eq!(
+            solve(&board).unwrap().to_clean_string(),
+            "123456789578961234931687452764892315245173968386245197659324871812739546497518623"
+        );
+    }
+
+    #[test]
+    fn solves_windoku() {
+        let mut board = Board::from_string(
+            "020400700000089003000000400500097000000000000807560000000008600000010000000000010",
+        )
+        .unwrap();
+        assert!(count_solutions(&board, 2) > 1);
+
+        board.add_windoku();
+        assert_eq!(count_solutions(&board, 2), 1);
+        assert_eq!(
+            solve(&board).unwrap().to_clean_string(),
+            "123456789456789123789123456534297861261834597897561234912378645348615972675942318"
+        );
+    }
+
+    /// Top-left cell must hold an even digit
+    #[derive(Debug)]
+    struct EvenCorner;
+
+    impl Constraint for EvenCorner {
+        fn violations(&self, grid: &Grid) -> Vec<Violation> {
+            return match grid[0][0] {
+                Some(num) if num % 2 == 1 => vec![Violation {
+                    rule: "even corner".to_string(),
+                    cells: vec![(0, 0)],
+                }],
+                _ => Vec::new(),
+            };
+        }
+
+        fn penalty(&self, grid: &Grid) -> u32 {
+            return self.violations(grid).len() as u32;
+        }
+
+        fn propagate(&self, _grid: &Grid, candidates: &mut Candidates) {
+            candidates.retain(0, 0, 0b0_1010_1010);
+        }
+    }
+
+    #[test]
+    fn follows_added_constraints() {
+        let mut board = Board::default();
+        board.add_constraint(EvenCorner);
+        let mut rng = StdRng::seed_from_u64(7);
+        for _ in 0..5 {
+            let solved = solve_random(&board, &mut rng).unwrap();
+            assert_eq!(solved.get(0, 0).unwrap() % 2, 0);
+        }
+
+        board.set(0, 0, Some(3));
+        assert!(solve(&board).is_err());
+    }
+}
@@ -0,0 +1,50 @@
+pub mod generator {
+    use rand::RngCore;
+    use rand::seq::SliceRandom;
+
+    use crate::exact::exact::{has_unique_solution, solve_random};
+    use crate::sudoku::sudoku::{BOARD_SIZE, Board};
+
+    /// Generates a puzzle with a unique solution by filling a random grid and removing clues
+    /// while the solution stays unique. Stops early once only `min_clues` clues are left.
+    pub fn generate(rng: &mut dyn RngCore, min_clues: usize) -> Board {
+        let mut board =
+            solve_random(&Board::default(), rng).expect("an empty board always has a solution");
+
+        let mut cells: Vec<usize> = (0..BOARD_SIZE * BOARD_SIZE).collect();
+        cells.shuffle(rng);
+
+        for cell in cells {
+            if board.clue_count() <= min_clues {
+                break;
+            }
+            let (row, col) = (cell / BOARD_SIZE, cell % BOARD_SIZE);
+            let removed = board.get(row, col);
+            board.set(row, col, None);
+            if !has_unique_solution(&board) {
+                board.set(row, col, removed);
+            }
+        }
+
+        return board;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    use crate::exact::exact::has_unique_solution;
+    use crate::generator::generator::generate;
+
+    #[test]
+    fn generated_puzzles_are_unique_and_seeded() {
+        let first = generate(&mut StdRng::seed_from_u64(7), 0);
+        let second = generate(&mut StdRng::seed_from_u64(7), 0);
+
+        assert!(has_unique_solution(&first));
+        assert_eq!(first.to_clean_string(), second.to_clean_string());
+        assert!(first.clue_count() < 40);
+    }
+}
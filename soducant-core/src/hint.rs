@@ -0,0 +1,62 @@
+pub mod hint {
+    use crate::constraint::constraint::ALL_DIGITS;
+    use crate::exact::exact::solve;
+    use crate::sudoku::sudoku::{BOARD_SIZE, Board};
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Hint {
+        pub row: usize,
+        pub col: usize,
+        pub digit: i8,
+    }
+
+    fn candidate_count(board: &Board, row: usize, col: usize) -> usize {
+        let used = board
+            .houses()
+            .iter()
+            .filter(|house| house.contains((row, col)))
+            .fold(0, |used, house| used | house.used(&board.grid));
+        return (!used & ALL_DIGITS).count_ones() as usize;
+    }
+
+    /// Suggests the value of the empty cell with the fewest candidates, or `None` if the
+    /// board is already full
+    pub fn hint(board: &Board) -> Result<Option<Hint>, Box<dyn std::error::Error>> {
+        let solved = solve(board)?;
+
+        let mut best: Option<(usize, Hint)> = None;
+        for row in 0..BOARD_SIZE {
+            for col in 0..BOARD_SIZE {
+                if board.get(row, col).is_some() {
+                    continue;
+                }
+                let count = candidate_count(board, row, col);
+                if best.is_none_or(|(c, _)| count < c) {
+                    let digit = solved.get(row, col).ok_or("Solver left a cell empty")?;
+                    best = Some((count, Hint { row, col, digit }));
+                }
+            }
+        }
+
+        return Ok(best.map(|(_, hint)| hint));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hint::hint::hint;
+    use crate::sudoku::sudoku::Board;
+
+    #[test]
+    fn hints_a_naked_single() {
+        let board = Board::from_string(
+            "278000401609100050005006900430809000706003000091000800000020173860001004107934685",
+        )
+        .unwrap();
+
+        let hint = hint(&board).unwrap().unwrap();
+
+        // First cell in reading order with a single candidate
+        assert_eq!((hint.row, hint.col, hint.digit), (0, 5, 5));
+    }
+}
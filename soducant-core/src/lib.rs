@@ -1,9 +1,26 @@
+#![forbid(unsafe_code)]
+#![allow(clippy::needless_return, clippy::module_inception)]
+
 pub mod sudoku;
+
+pub mod constraint;
 // use crate::solver::solver::benchmark;
 // use crate::solver::solver::Solver;
 // use crate::sudoku::sudoku::Board;
 
 pub mod solver;
+pub mod tuning;
+
+pub mod exact;
+pub mod generator;
+pub mod hint;
+pub mod logic;
+pub mod multigrid;
+pub mod rating;
+pub mod rules;
+pub mod save;
+pub mod session;
+pub mod variants;
 
 /*fn main() {
     let board = Board::from_string(
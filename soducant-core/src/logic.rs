@@ -0,0 +1,1967 @@
+pub mod logic {
+    use serde::{Deserialize, Serialize};
+    use std::fmt::{Display, Formatter};
+    use std::str::FromStr;
+
+    use crate::constraint::constraint::{ALL_DIGITS, Candidates, Constraint, House, digit_bit};
+    use crate::exact::exact::count_solutions;
+    use crate::sudoku::sudoku::{BOARD_SIZE, Board};
+
+    /// Houses of a board come as rows, then columns, then regions, then anything else
+    const LINES: usize = 2 * BOARD_SIZE;
+
+    /// A human solving technique
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+    #[serde(rename_all = "kebab-case")]
+    pub enum Technique {
+        /// The only place left for a digit in a house
+        HiddenSingle,
+        /// The only digit left for a cell
+        NakedSingle,
+        /// Candidates removed by a variant rule, such as a cage sum or a thermometer
+        VariantRule,
+        /// A digit confined to one line within a region is removed from the rest of the line
+        Pointing,
+        /// A digit confined to one region within a line is removed from the rest of the region
+        Claiming,
+        NakedPair,
+        HiddenPair,
+        NakedTriple,
+        HiddenTriple,
+        NakedQuad,
+        HiddenQuad,
+        /// A digit confined to the same two columns in two rows, or the other way round, is
+        /// removed from the rest of those columns
+        XWing,
+        Swordfish,
+        Jellyfish,
+        /// A fish with extra candidates, the fins, all in one region. Only cells that also see
+        /// the fins lose the digit.
+        FinnedXWing,
+        FinnedSwordfish,
+        FinnedJellyfish,
+        /// A finned fish that would be missing a corner without its fins
+        SashimiXWing,
+        SashimiSwordfish,
+        SashimiJellyfish,
+        /// A two-digit pivot seeing two two-digit pincers that share a third digit
+        #[serde(rename = "xy-wing")]
+        XYWing,
+        /// An XY-Wing whose pivot also holds the shared digit
+        #[serde(rename = "xyz-wing")]
+        XYZWing,
+        /// Two cells with the same two digits, joined by a strong link on one of them
+        WWing,
+        /// Four cells in two rows, two columns and two regions can't all be left with the
+        /// same two digits, as those could then be swapped. Type 1: three of them have just
+        /// the two, so the fourth loses them.
+        #[serde(rename = "unique-rectangle-1")]
+        UniqueRectangle1,
+        /// Two cells of a rectangle with just the two digits and the other two with the same
+        /// one extra digit, which cells seeing both of those then can't take
+        #[serde(rename = "unique-rectangle-2")]
+        UniqueRectangle2,
+        /// The extra digits of two cells of a rectangle form a naked subset with other cells
+        /// of a house
+        #[serde(rename = "unique-rectangle-3")]
+        UniqueRectangle3,
+        /// One of the two digits only fits the extra cells of a rectangle in a house, so they
+        /// lose the other
+        #[serde(rename = "unique-rectangle-4")]
+        UniqueRectangle4,
+        /// Every empty cell has two candidates but one with three. Taking the digit that
+        /// shows up three times in its houses is the only way out of a deadly pattern.
+        BugPlusOne,
+        /// An alternating chain on one digit. One of its ends holds the digit, so cells
+        /// seeing both ends can't.
+        XChain,
+        /// A chain of two-digit cells starting and ending on the same digit, which cells
+        /// seeing both ends can't hold
+        #[serde(rename = "xy-chain")]
+        XYChain,
+        /// An alternating inference chain: links alternate between strong (one of two
+        /// candidates is true) and weak (at most one is), starting and ending with a strong
+        /// one. One of the ends is true, so anything weakly linked to both is false.
+        #[serde(rename = "aic")]
+        AIC,
+        /// A chain from a candidate back to itself, which is false if it ends with a weak link
+        /// and true if it ends with a strong one
+        NiceLoop,
+        /// Whichever candidate a cell takes, chains from it all reach the same conclusion
+        CellForcingChain,
+        /// Wherever a digit goes in a house, chains from there all reach the same conclusion
+        HouseForcingChain,
+    }
+
+    impl Technique {
+        /// Every technique, in the order the solver tries them
+        pub const ALL: [Technique; 34] = [
+            Technique::HiddenSingle,
+            Technique::NakedSingle,
+            Technique::VariantRule,
+            Technique::Pointing,
+            Technique::Claiming,
+            Technique::NakedPair,
+            Technique::HiddenPair,
+            Technique::NakedTriple,
+            Technique::HiddenTriple,
+            Technique::XWing,
+            Technique::FinnedXWing,
+            Technique::SashimiXWing,
+            Technique::XYWing,
+            Technique::XYZWing,
+            Technique::WWing,
+            Technique::UniqueRectangle1,
+            Technique::UniqueRectangle2,
+            Technique::UniqueRectangle3,
+            Technique::UniqueRectangle4,
+            Technique::NakedQuad,
+            Technique::HiddenQuad,
+            Technique::Swordfish,
+            Technique::FinnedSwordfish,
+            Technique::SashimiSwordfish,
+            Technique::Jellyfish,
+            Technique::FinnedJellyfish,
+            Technique::SashimiJellyfish,
+            Technique::BugPlusOne,
+            Technique::XChain,
+            Technique::XYChain,
+            Technique::AIC,
+            Technique::NiceLoop,
+            Technique::CellForcingChain,
+            Technique::HouseForcingChain,
+        ];
+
+        pub fn name(&self) -> &'static str {
+            return match self {
+                Technique::HiddenSingle => "Hidden single",
+                Technique::NakedSingle => "Naked single",
+                Technique::VariantRule => "Variant rule",
+                Technique::Pointing => "Pointing",
+                Technique::Claiming => "Claiming",
+                Technique::NakedPair => "Naked pair",
+                Technique::HiddenPair => "Hidden pair",
+                Technique::NakedTriple => "Naked triple",
+                Technique::HiddenTriple => "Hidden triple",
+                Technique::NakedQuad => "Naked quad",
+                Technique::HiddenQuad => "Hidden quad",
+                Technique::XWing => "X-Wing",
+                Technique::Swordfish => "Swordfish",
+                Technique::Jellyfish => "Jellyfish",
+                Technique::FinnedXWing => "Finned X-Wing",
+                Technique::FinnedSwordfish => "Finned swordfish",
+                Technique::FinnedJellyfish => "Finned jellyfish",
+                Technique::SashimiXWing => "Sashimi X-Wing",
+                Technique::SashimiSwordfish => "Sashimi swordfish",
+                Technique::SashimiJellyfish => "Sashimi jellyfish",
+                Technique::XYWing => "XY-Wing",
+                Technique::XYZWing => "XYZ-Wing",
+                Technique::WWing => "W-Wing",
+                Technique::UniqueRectangle1 => "Unique rectangle type 1",
+                Technique::UniqueRectangle2 => "Unique rectangle type 2",
+                Technique::UniqueRectangle3 => "Unique rectangle type 3",
+                Technique::UniqueRectangle4 => "Unique rectangle type 4",
+                Technique::BugPlusOne => "BUG+1",
+                Technique::XChain => "X-Chain",
+                Technique::XYChain => "XY-Chain",
+                Technique::AIC => "AIC",
+                Technique::NiceLoop => "Nice loop",
+                Technique::CellForcingChain => "Cell forcing chain",
+                Technique::HouseForcingChain => "House forcing chain",
+            };
+        }
+
+        /// Whether the technique only works on puzzles with one solution
+        pub fn needs_uniqueness(&self) -> bool {
+            return matches!(
+                self,
+                Technique::UniqueRectangle1
+                    | Technique::UniqueRectangle2
+                    | Technique::UniqueRectangle3
+                    | Technique::UniqueRectangle4
+                    | Technique::BugPlusOne
+            );
+        }
+    }
+
+    impl Display for Technique {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{}", self.name())
+        }
+    }
+
+    /// Reads the names used in JSON output, such as `hidden-single` or `xy-wing`
+    impl FromStr for Technique {
+        type Err = Box<dyn std::error::Error>;
+
+        fn from_str(text: &str) -> Result<Self, Self::Err> {
+            let name = serde_json::Value::String(text.trim().to_string());
+            return serde_json::from_value(name)
+                .map_err(|_| Box::from(format!("Unknown technique: {}", text)));
+        }
+    }
+
+    /// One deduction. Placing a digit also removes it from the cell's houses; those
+    /// eliminations aren't listed.
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+    pub struct Step {
+        pub technique: Technique,
+        /// (row, col, digit) placed, 0-based
+        pub placement: Option<(usize, usize, i8)>,
+        /// (row, col, digit) candidates removed, 0-based
+        pub eliminations: Vec<(usize, usize, i8)>,
+        /// Cells the pattern is made of, such as the two cells of a naked pair
+        pub cells: Vec<(usize, usize)>,
+        /// Houses the pattern lives in, e.g. "row 3"
+        pub houses: Vec<String>,
+        /// Chains behind the step. Forcing chains have one per branch.
+        pub chains: Vec<Chain>,
+    }
+
+    impl Step {
+        fn new(technique: Technique) -> Self {
+            return Step {
+                technique,
+                placement: None,
+                eliminations: Vec::new(),
+                cells: Vec::new(),
+                houses: Vec::new(),
+                chains: Vec::new(),
+            };
+        }
+    }
+
+    /// Written like `Naked pair in row 1 (r1c2 r1c5): r1c3<>1 r1c9<>7`
+    impl Display for Step {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{}", self.technique)?;
+            if !self.houses.is_empty() {
+                write!(f, " in {}", self.houses.join(" and "))?;
+            }
+            if !self.cells.is_empty() {
+                let cells: Vec<String> = self
+                    .cells
+                    .iter()
+                    .map(|(i, j)| format!("r{}c{}", i + 1, j + 1))
+                    .collect();
+                write!(f, " ({})", cells.join(" "))?;
+            }
+            for chain in &self.chains {
+                write!(f, " [{}]", chain)?;
+            }
+            write!(f, ":")?;
+            if let Some((i, j, digit)) = self.placement {
+                write!(f, " r{}c{}={}", i + 1, j + 1, digit)?;
+            }
+            for (i, j, digit) in &self.eliminations {
+                write!(f, " r{}c{}<>{}", i + 1, j + 1, digit)?;
+            }
+            Ok(())
+        }
+    }
+
+    /// Candidates joined by links that alternate between strong and weak
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+    pub struct Chain {
+        /// (row, col, digit) candidates, 0-based
+        pub nodes: Vec<(usize, usize, i8)>,
+        /// Whether the link between the first two nodes is strong. Forcing chains start
+        /// from a candidate assumed true, and so with a weak link.
+        pub strong_first: bool,
+    }
+
+    /// Written in Eureka notation, like `(5)r1c2=(5)r1c8-(7)r1c8=(7)r4c8`
+    impl Display for Chain {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            for (k, (i, j, digit)) in self.nodes.iter().enumerate() {
+                if k > 0 {
+                    let strong = (k % 2 == 1) == self.strong_first;
+                    write!(f, "{}", if strong { "=" } else { "-" })?;
+                }
+                write!(f, "({})r{}c{}", digit, i + 1, j + 1)?;
+            }
+            Ok(())
+        }
+    }
+
+    /// Everything the logical solver did
+    #[derive(Debug, Clone)]
+    pub struct Trace {
+        pub steps: Vec<Step>,
+        /// The board after the last step
+        pub board: Board,
+        /// Candidates of the empty cells after the last step
+        pub candidates: Candidates,
+    }
+
+    impl Trace {
+        /// False when the solver stalled: no enabled technique applies to `board`
+        pub fn is_solved(&self) -> bool {
+            return self.board.is_complete();
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct LogicOptions {
+        /// Techniques to use, tried in this order
+        pub techniques: Vec<Technique>,
+        /// Longest chain searched, in links. Longer chains find more but take longer.
+        pub max_chain_length: usize,
+        /// The puzzle is known to have one solution, which allows techniques that rely on it.
+        /// They are skipped otherwise.
+        pub unique: bool,
+    }
+
+    impl Default for LogicOptions {
+        fn default() -> Self {
+            return LogicOptions {
+                techniques: Technique::ALL.to_vec(),
+                max_chain_length: 12,
+                unique: false,
+            };
+        }
+    }
+
+    /// Solves like a person would, with the default techniques
+    pub fn solve_logically(board: &Board) -> Result<Trace, Box<dyn std::error::Error>> {
+        return solve_logically_with(board, &LogicOptions::default());
+    }
+
+    /// Applies the first technique that makes progress until the board is full or none does.
+    /// Fails if the board contradicts itself, such as a cell left without candidates.
+    pub fn solve_logically_with(
+        board: &Board,
+        options: &LogicOptions,
+    ) -> Result<Trace, Box<dyn std::error::Error>> {
+        check_conflicts(board)?;
+        let mut state = State::new(board);
+        let mut steps = Vec::new();
+        while !state.board.is_complete() {
+            state.check()?;
+            let step = options
+                .techniques
+                .iter()
+                .filter(|technique| options.unique || !technique.needs_uniqueness())
+                .find_map(|&technique| state.find(technique, options));
+            let Some(step) = step else {
+                break;
+            };
+            state.apply(&step);
+            steps.push(step);
+        }
+        return Ok(Trace {
+            steps,
+            board: state.board,
+            candidates: state.candidates,
+        });
+    }
+
+    /// A rating on the scale of Sudoku Explainer (SE), from 1.2 for puzzles that only need
+    /// hidden singles in regions to past 11 for the hardest known
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct SeRating {
+        /// Difficulty of the hardest step
+        pub rating: f64,
+        /// Steps taken, each with its SE difficulty
+        pub steps: Vec<(Step, f64)>,
+        /// False when the puzzle needs a technique SE has and this solver doesn't, such as
+        /// dynamic forcing chains. The rating is then only a lower bound.
+        pub complete: bool,
+    }
+
+    /// Rates with SE's technique order and weights: each time the easiest step SE knows of is
+    /// taken, and the puzzle is as hard as its hardest step. Techniques SE lacks, such as
+    /// finned fish and W-Wings, are left to chains like in SE. Unique rectangles and BUG+1 are
+    /// used, so the puzzle must have a single solution. The ratings haven't been checked
+    /// against SE's own, and can differ from them where SE finds other chains.
+    pub fn se_rating(board: &Board) -> Result<SeRating, Box<dyn std::error::Error>> {
+        check_conflicts(board)?;
+        if count_solutions(board, 2) != 1 {
+            return Err(Box::from("SE ratings need a puzzle with a single solution"));
+        }
+        let options = LogicOptions {
+            unique: true,
+            ..LogicOptions::default()
+        };
+        let mut state = State::new(board);
+        let mut steps = Vec::new();
+        while !state.board.is_complete() {
+            state.check()?;
+            let Some((step, tenths)) = state.se_step(&options) else {
+                break;
+            };
+            state.apply(&step);
+            steps.push((step, tenths as f64 / 10.0));
+        }
+        return Ok(SeRating {
+            rating: steps.iter().map(|(_, rating)| *rating).fold(0.0, f64::max),
+            steps,
+            complete: state.board.is_complete(),
+        });
+    }
+
+    fn check_conflicts(board: &Board) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some([(i1, j1), (i2, j2)]) = board.conflicts().first() {
+            return Err(Box::from(format!(
+                "r{}c{} and r{}c{} hold the same digit",
+                i1 + 1,
+                j1 + 1,
+                i2 + 1,
+                j2 + 1
+            )));
+        }
+        return Ok(());
+    }
+
+    /// SE's difficulties in tenths, easiest first, for the techniques it rates at a fixed
+    /// value. Type 3 unique rectangles add a tenth for each cell of their subset.
+    const SE_DIFFICULTIES: [(Technique, u32); 21] = [
+        (Technique::Pointing, 26),
+        (Technique::Claiming, 28),
+        (Technique::NakedPair, 30),
+        (Technique::XWing, 32),
+        (Technique::HiddenPair, 34),
+        (Technique::NakedTriple, 36),
+        (Technique::Swordfish, 38),
+        (Technique::HiddenTriple, 40),
+        (Technique::XYWing, 42),
+        (Technique::XYZWing, 44),
+        (Technique::UniqueRectangle1, 45),
+        (Technique::UniqueRectangle3, 45),
+        (Technique::UniqueRectangle2, 46),
+        (Technique::UniqueRectangle4, 46),
+        (Technique::NakedQuad, 50),
+        (Technique::Jellyfish, 52),
+        (Technique::HiddenQuad, 54),
+        (Technique::BugPlusOne, 56),
+        (Technique::XChain, 66),
+        (Technique::AIC, 66),
+        (Technique::NiceLoop, 66),
+    ];
+
+    struct State {
+        board: Board,
+        candidates: Candidates,
+        /// Cells sharing a house with each cell, as bits `row * 9 + col`
+        peers: [u128; BOARD_SIZE * BOARD_SIZE],
+    }
+
+    fn bit((i, j): (usize, usize)) -> u128 {
+        return 1 << (i * BOARD_SIZE + j);
+    }
+
+    impl State {
+        /// Candidates left by the digits in each house. Variant rules are left to
+        /// [`Technique::VariantRule`] so that their eliminations show up in the trace.
+        fn new(board: &Board) -> Self {
+            let mut candidates = Candidates::all();
+            for house in board.houses() {
+                house.propagate(&board.grid, &mut candidates);
+            }
+            for i in 0..BOARD_SIZE {
+                for j in 0..BOARD_SIZE {
+                    if board.get(i, j).is_some() {
+                        candidates.set(i, j, 0);
+                    }
+                }
+            }
+            let mut peers = [0; BOARD_SIZE * BOARD_SIZE];
+            for house in board.houses() {
+                let all = house.cells.iter().fold(0, |all, &cell| all | bit(cell));
+                for &(i, j) in &house.cells {
+                    peers[i * BOARD_SIZE + j] |= all & !bit((i, j));
+                }
+            }
+            return State {
+                board: board.clone(),
+                candidates,
+                peers,
+            };
+        }
+
+        fn sees(&self, (i, j): (usize, usize), other: (usize, usize)) -> bool {
+            return self.peers[i * BOARD_SIZE + j] & bit(other) != 0;
+        }
+
+        /// Empty cells with exactly two candidates
+        fn bivalue_cells(&self) -> Vec<(usize, usize)> {
+            return self.cells_with(2);
+        }
+
+        fn cells_with(&self, count: u32) -> Vec<(usize, usize)> {
+            let mut out = Vec::new();
+            for i in 0..BOARD_SIZE {
+                for j in 0..BOARD_SIZE {
+                    if self.is_empty((i, j)) && self.candidates.count(i, j) == count {
+                        out.push((i, j));
+                    }
+                }
+            }
+            return out;
+        }
+
+        /// Removes the digits in `mask` from every empty cell that sees all of `cells`
+        fn eliminate_seen_by(
+            &self,
+            cells: &[(usize, usize)],
+            mask: u16,
+        ) -> Vec<(usize, usize, i8)> {
+            let mut out = Vec::new();
+            for i in 0..BOARD_SIZE {
+                for j in 0..BOARD_SIZE {
+                    if !cells.iter().all(|&cell| self.sees(cell, (i, j))) {
+                        continue;
+                    }
+                    for digit in self.candidates.digits(i, j) {
+                        if mask & digit_bit(digit) != 0 {
+                            out.push((i, j, digit));
+                        }
+                    }
+                }
+            }
+            return out;
+        }
+
+        fn is_empty(&self, (i, j): (usize, usize)) -> bool {
+            return self.board.get(i, j).is_none();
+        }
+
+        fn mask(&self, (i, j): (usize, usize)) -> u16 {
+            return self.candidates.get(i, j);
+        }
+
+        /// Empty cells of a house that may still hold `digit`
+        fn places(&self, house: &House, digit: i8) -> Vec<(usize, usize)> {
+            return house
+                .cells
+                .iter()
+                .copied()
+                .filter(|&cell| self.mask(cell) & digit_bit(digit) != 0)
+                .collect();
+        }
+
+        /// Fails on a cell without candidates or a digit without a place in a house
+        fn check(&self) -> Result<(), Box<dyn std::error::Error>> {
+            for i in 0..BOARD_SIZE {
+                for j in 0..BOARD_SIZE {
+                    if self.is_empty((i, j)) && self.candidates.count(i, j) == 0 {
+                        return Err(Box::from(format!(
+                            "r{}c{} has no candidates left",
+                            i + 1,
+                            j + 1
+                        )));
+                    }
+                }
+            }
+            for house in self.board.houses() {
+                let missing = !house.used(&self.board.grid) & ALL_DIGITS;
+                for digit in 1..=BOARD_SIZE as i8 {
+                    if missing & digit_bit(digit) != 0 && self.places(house, digit).is_empty() {
+                        return Err(Box::from(format!(
+                            "{} has no place left in {}",
+                            digit, house.name
+                        )));
+                    }
+                }
+            }
+            return Ok(());
+        }
+
+        fn apply(&mut self, step: &Step) {
+            for &(i, j, digit) in &step.eliminations {
+                self.candidates.eliminate(i, j, digit);
+            }
+            if let Some((row, col, digit)) = step.placement {
+                self.board.set(row, col, Some(digit));
+                self.candidates.set(row, col, 0);
+                for house in self.board.houses() {
+                    if house.contains((row, col)) {
+                        for &(i, j) in &house.cells {
+                            self.candidates.eliminate(i, j, digit);
+                        }
+                    }
+                }
+            }
+        }
+
+        fn find(&self, technique: Technique, options: &LogicOptions) -> Option<Step> {
+            let cap = options.max_chain_length;
+            return match technique {
+                Technique::HiddenSingle => self.hidden_single(self.board.houses()),
+                Technique::NakedSingle => self.naked_single(),
+                Technique::VariantRule => self.variant_rule(),
+                Technique::Pointing | Technique::Claiming => {
+                    self.locked_candidates(technique, &|_| true)
+                }
+                Technique::NakedPair => self.naked_subset(technique, 2),
+                Technique::NakedTriple => self.naked_subset(technique, 3),
+                Technique::NakedQuad => self.naked_subset(technique, 4),
+                Technique::HiddenPair => self.hidden_subset(technique, 2, &|_| true),
+                Technique::HiddenTriple => self.hidden_subset(technique, 3, &|_| true),
+                Technique::HiddenQuad => self.hidden_subset(technique, 4, &|_| true),
+                Technique::XWing | Technique::FinnedXWing | Technique::SashimiXWing => {
+                    self.fish(technique, 2)
+                }
+                Technique::Swordfish | Technique::FinnedSwordfish | Technique::SashimiSwordfish => {
+                    self.fish(technique, 3)
+                }
+                Technique::Jellyfish | Technique::FinnedJellyfish | Technique::SashimiJellyfish => {
+                    self.fish(technique, 4)
+                }
+                Technique::XYWing => self.xy_wing(),
+                Technique::XYZWing => self.xyz_wing(),
+                Technique::WWing => self.w_wing(),
+                Technique::UniqueRectangle1
+                | Technique::UniqueRectangle2
+                | Technique::UniqueRectangle3
+                | Technique::UniqueRectangle4 => self.unique_rectangle(technique),
+                Technique::BugPlusOne => self.bug_plus_one(),
+                Technique::XChain => {
+                    let links = self.links(|_, a, b| a % 9 == b % 9);
+                    self.chain(technique, &links, cap)
+                }
+                Technique::XYChain => {
+                    let links = self.links(|strong, a, b| {
+                        let bivalue = |node: usize| self.mask(cell_of(node)).count_ones() == 2;
+                        let same_cell = a / 9 == b / 9;
+                        return bivalue(a) && bivalue(b) && strong == same_cell;
+                    });
+                    self.chain(technique, &links, cap)
+                }
+                Technique::AIC => self.chain(technique, &self.links(|_, _, _| true), cap),
+                Technique::NiceLoop => self.nice_loop(cap),
+                Technique::CellForcingChain | Technique::HouseForcingChain => {
+                    self.forcing_chain(technique, cap)
+                }
+            };
+        }
+
+        fn hidden_single(&self, houses: &[House]) -> Option<Step> {
+            for house in houses {
+                for digit in 1..=BOARD_SIZE as i8 {
+                    if let [(i, j)] = self.places(house, digit)[..] {
+                        let mut step = Step::new(Technique::HiddenSingle);
+                        step.placement = Some((i, j, digit));
+                        step.cells = vec![(i, j)];
+                        step.cells.extend(self.blockers(house, (i, j), digit));
+                        step.houses.push(house.name.clone());
+                        return Some(step);
+                    }
+                }
+            }
+            return None;
+        }
+
+        /// Cells holding `digit` that keep it out of the house's other empty cells
+        fn blockers(&self, house: &House, place: (usize, usize), digit: i8) -> Vec<(usize, usize)> {
+            let holders: Vec<(usize, usize)> = (0..BOARD_SIZE * BOARD_SIZE)
+                .map(|n| (n / BOARD_SIZE, n % BOARD_SIZE))
+                .filter(|&(i, j)| self.board.get(i, j) == Some(digit))
+                .collect();
+            let mut out = Vec::new();
+            for &cell in &house.cells {
+                if cell == place || !self.is_empty(cell) {
+                    continue;
+                }
+                let blocker = holders.iter().find(|&&holder| self.sees(holder, cell));
+                if let Some(&blocker) = blocker
+                    && !out.contains(&blocker)
+                {
+                    out.push(blocker);
+                }
+            }
+            return out;
+        }
+
+        fn naked_single(&self) -> Option<Step> {
+            for i in 0..BOARD_SIZE {
+                for j in 0..BOARD_SIZE {
+                    if self.candidates.count(i, j) == 1 {
+                        let digit = self.candidates.digits(i, j).next()?;
+                        let mut step = Step::new(Technique::NakedSingle);
+                        step.placement = Some((i, j, digit));
+                        step.cells = vec![(i, j)];
+                        return Some(step);
+                    }
+                }
+            }
+            return None;
+        }
+
+        /// Candidates the board's other rules rule out, all in one step, with the cells they
+        /// narrowed
+        fn variant_rule(&self) -> Option<Step> {
+            let mut narrowed = self.candidates;
+            for constraint in self.board.variant_constraints() {
+                constraint.propagate(&self.board.grid, &mut narrowed);
+            }
+            let mut step = Step::new(Technique::VariantRule);
+            step.eliminations = self.removed(&narrowed);
+            for &(i, j, _) in &step.eliminations {
+                if !step.cells.contains(&(i, j)) {
+                    step.cells.push((i, j));
+                }
+            }
+            return (!step.eliminations.is_empty()).then_some(step);
+        }
+
+        /// Candidates of empty cells that `narrowed` no longer has
+        fn removed(&self, narrowed: &Candidates) -> Vec<(usize, usize, i8)> {
+            let mut out = Vec::new();
+            for i in 0..BOARD_SIZE {
+                for j in 0..BOARD_SIZE {
+                    let gone = self.candidates.get(i, j) & !narrowed.get(i, j);
+                    for digit in 1..=BOARD_SIZE as i8 {
+                        if gone & digit_bit(digit) != 0 {
+                            out.push((i, j, digit));
+                        }
+                    }
+                }
+            }
+            return out;
+        }
+
+        /// A digit whose places in one house all lie in another is removed from the rest of
+        /// the other. Pointing starts from a region (or diagonal or extra region), claiming
+        /// from a row or column.
+        fn locked_candidates(
+            &self,
+            technique: Technique,
+            accept: &dyn Fn(&Step) -> bool,
+        ) -> Option<Step> {
+            let houses = self.board.houses();
+            for (a, inside) in houses.iter().enumerate() {
+                if (a < LINES) != (technique == Technique::Claiming) {
+                    continue;
+                }
+                for (b, outside) in houses.iter().enumerate() {
+                    // Two lines share at most one cell, which would be a hidden single
+                    if a == b || (a < LINES && b < LINES) {
+                        continue;
+                    }
+                    for digit in 1..=BOARD_SIZE as i8 {
+                        let places = self.places(inside, digit);
+                        if places.len() < 2 || !places.iter().all(|&cell| outside.contains(cell)) {
+                            continue;
+                        }
+                        let eliminations: Vec<(usize, usize, i8)> = self
+                            .places(outside, digit)
+                            .into_iter()
+                            .filter(|cell| !places.contains(cell))
+                            .map(|(i, j)| (i, j, digit))
+                            .collect();
+                        if eliminations.is_empty() {
+                            continue;
+                        }
+                        let mut step = Step::new(technique);
+                        step.eliminations = eliminations;
+                        step.cells = places;
+                        step.houses = vec![inside.name.clone(), outside.name.clone()];
+                        if accept(&step) {
+                            return Some(step);
+                        }
+                    }
+                }
+            }
+            return None;
+        }
+
+        /// `size` cells of a house with only `size` digits between them, which the other
+        /// cells of the house can't take
+        fn naked_subset(&self, technique: Technique, size: u32) -> Option<Step> {
+            for house in self.board.houses() {
+                for pick in subsets(size) {
+                    let cells: Vec<(usize, usize)> = picked(pick, &house.cells);
+                    let usable = cells.iter().all(|&cell| {
+                        self.is_empty(cell) && (2..=size).contains(&self.mask(cell).count_ones())
+                    });
+                    let digits = cells.iter().fold(0, |all, &cell| all | self.mask(cell));
+                    if !usable || digits.count_ones() != size {
+                        continue;
+                    }
+                    let mut eliminations = Vec::new();
+                    for &(i, j) in house.cells.iter().filter(|cell| !cells.contains(cell)) {
+                        for digit in self.candidates.digits(i, j) {
+                            if digits & digit_bit(digit) != 0 {
+                                eliminations.push((i, j, digit));
+                            }
+                        }
+                    }
+                    if !eliminations.is_empty() {
+                        let mut step = Step::new(technique);
+                        step.eliminations = eliminations;
+                        step.cells = cells;
+                        step.houses.push(house.name.clone());
+                        return Some(step);
+                    }
+                }
+            }
+            return None;
+        }
+
+        /// `size` digits of a house with only `size` places between them, so those cells
+        /// can't take any other digit
+        fn hidden_subset(
+            &self,
+            technique: Technique,
+            size: u32,
+            accept: &dyn Fn(&Step) -> bool,
+        ) -> Option<Step> {
+            for house in self.board.houses() {
+                let missing = !house.used(&self.board.grid) & ALL_DIGITS;
+                for digits in subsets(size).filter(|&digits| digits & !missing == 0) {
+                    let cells: Vec<(usize, usize)> = house
+                        .cells
+                        .iter()
+                        .copied()
+                        .filter(|&cell| self.mask(cell) & digits != 0)
+                        .collect();
+                    if cells.len() != size as usize {
+                        continue;
+                    }
+                    let mut eliminations = Vec::new();
+                    for &(i, j) in &cells {
+                        for digit in self.candidates.digits(i, j) {
+                            if digits & digit_bit(digit) == 0 {
+                                eliminations.push((i, j, digit));
+                            }
+                        }
+                    }
+                    if eliminations.is_empty() {
+                        continue;
+                    }
+                    let mut step = Step::new(technique);
+                    step.eliminations = eliminations;
+                    step.cells = cells;
+                    step.houses.push(house.name.clone());
+                    if accept(&step) {
+                        return Some(step);
+                    }
+                }
+            }
+            return None;
+        }
+
+        /// `size` rows whose places for a digit lie in `size` columns between them, which
+        /// then can't hold the digit anywhere else; or the same with rows and columns swapped.
+        /// Finned and sashimi fish allow extra places in one region.
+        fn fish(&self, technique: Technique, size: u32) -> Option<Step> {
+            let (finned, _) = fish_kind(technique);
+            for digit in 1..=BOARD_SIZE as i8 {
+                for by_columns in [false, true] {
+                    // Places of the digit along each line, as bits
+                    let spots: [u16; BOARD_SIZE] = std::array::from_fn(|line| {
+                        (0..BOARD_SIZE)
+                            .filter(|&pos| {
+                                self.mask(fish_cell(by_columns, line, pos)) & digit_bit(digit) != 0
+                            })
+                            .fold(0, |spots, pos| spots | 1 << pos)
+                    });
+                    for base in subsets(size) {
+                        if picked(base, &spots).contains(&0) {
+                            continue;
+                        }
+                        let all = picked(base, &spots)
+                            .iter()
+                            .fold(0, |all, spots| all | spots);
+                        let covers: Vec<u16> = if finned {
+                            subsets(size).filter(|&cover| cover & !all == 0).collect()
+                        } else if all.count_ones() == size {
+                            vec![all]
+                        } else {
+                            continue;
+                        };
+                        for cover in covers {
+                            let fish = Fish {
+                                digit,
+                                by_columns,
+                                base,
+                                cover,
+                            };
+                            if let Some(step) = self.fish_step(technique, &fish, &spots) {
+                                return Some(step);
+                            }
+                        }
+                    }
+                }
+            }
+            return None;
+        }
+
+        /// The eliminations of one fish, or `None` if it has none or isn't of the right kind
+        fn fish_step(
+            &self,
+            technique: Technique,
+            fish: &Fish,
+            spots: &[u16; BOARD_SIZE],
+        ) -> Option<Step> {
+            let lines: Vec<usize> = (0..BOARD_SIZE)
+                .filter(|&l| fish.base & (1 << l) != 0)
+                .collect();
+            let cover: Vec<usize> = (0..BOARD_SIZE)
+                .filter(|&p| fish.cover & (1 << p) != 0)
+                .collect();
+            let cell = |line: usize, pos: usize| fish_cell(fish.by_columns, line, pos);
+            if lines.iter().any(|&line| spots[line] & fish.cover == 0) {
+                return None;
+            }
+            let mut fins = Vec::new();
+            for &line in &lines {
+                for pos in
+                    (0..BOARD_SIZE).filter(|&pos| spots[line] & !fish.cover & (1 << pos) != 0)
+                {
+                    fins.push(cell(line, pos));
+                }
+            }
+            let regions = self.board.regions();
+            let fin_region = fins.first().map(|&(i, j)| regions.region_of(i, j));
+            if fins
+                .iter()
+                .any(|&(i, j)| Some(regions.region_of(i, j)) != fin_region)
+            {
+                return None;
+            }
+            // Sashimi fish lose a corner without their fins, so some base line has one place
+            let (finned, sashimi) = fish_kind(technique);
+            let thin = lines
+                .iter()
+                .any(|&line| (spots[line] & fish.cover).count_ones() == 1);
+            if fin_region.is_some() != finned || (finned && thin != sashimi) {
+                return None;
+            }
+
+            let mut step = Step::new(technique);
+            for &pos in &cover {
+                for line in (0..BOARD_SIZE).filter(|line| !lines.contains(line)) {
+                    let (i, j) = cell(line, pos);
+                    let seen = fin_region.is_none_or(|region| regions.region_of(i, j) == region);
+                    if seen && spots[line] & (1 << pos) != 0 {
+                        step.eliminations.push((i, j, fish.digit));
+                    }
+                }
+            }
+            if step.eliminations.is_empty() {
+                return None;
+            }
+            for &line in &lines {
+                for pos in (0..BOARD_SIZE).filter(|&pos| spots[line] & (1 << pos) != 0) {
+                    step.cells.push(cell(line, pos));
+                }
+            }
+            let (base, covered) = if fish.by_columns {
+                ("column", "row")
+            } else {
+                ("row", "column")
+            };
+            step.houses = lines
+                .iter()
+                .map(|line| format!("{} {}", base, line + 1))
+                .chain(cover.iter().map(|pos| format!("{} {}", covered, pos + 1)))
+                .collect();
+            return Some(step);
+        }
+
+        /// Pivot `xy` seeing pincers `xz` and `yz`: a cell seeing both pincers can't be `z`
+        fn xy_wing(&self) -> Option<Step> {
+            let bivalue = self.bivalue_cells();
+            for &pivot in &bivalue {
+                let digits = self.mask(pivot);
+                for &a in bivalue.iter().filter(|&&a| self.sees(pivot, a)) {
+                    let shared = digits & self.mask(a);
+                    if shared.count_ones() != 1 {
+                        continue;
+                    }
+                    let z = self.mask(a) & !digits;
+                    for &b in bivalue.iter().filter(|&&b| self.sees(pivot, b)) {
+                        if self.mask(b) != (digits & !shared) | z {
+                            continue;
+                        }
+                        let eliminations = self.eliminate_seen_by(&[a, b], z);
+                        if !eliminations.is_empty() {
+                            let mut step = Step::new(Technique::XYWing);
+                            step.eliminations = eliminations;
+                            step.cells = vec![pivot, a, b];
+                            return Some(step);
+                        }
+                    }
+                }
+            }
+            return None;
+        }
+
+        /// Pivot `xyz` seeing pincers `xz` and `yz`: a cell seeing all three can't be `z`
+        fn xyz_wing(&self) -> Option<Step> {
+            let bivalue = self.bivalue_cells();
+            for pivot in self.cells_with(3) {
+                let digits = self.mask(pivot);
+                let pincers: Vec<(usize, usize)> = bivalue
+                    .iter()
+                    .copied()
+                    .filter(|&cell| self.sees(pivot, cell) && self.mask(cell) & !digits == 0)
+                    .collect();
+                for (k, &a) in pincers.iter().enumerate() {
+                    for &b in &pincers[k + 1..] {
+                        let z = self.mask(a) & self.mask(b);
+                        if z.count_ones() != 1 || self.mask(a) | self.mask(b) != digits {
+                            continue;
+                        }
+                        let eliminations = self.eliminate_seen_by(&[pivot, a, b], z);
+                        if !eliminations.is_empty() {
+                            let mut step = Step::new(Technique::XYZWing);
+                            step.eliminations = eliminations;
+                            step.cells = vec![pivot, a, b];
+                            return Some(step);
+                        }
+                    }
+                }
+            }
+            return None;
+        }
+
+        /// Two `xy` cells that don't see each other, with a house where `x` has two places,
+        /// one seen by each cell. One of the cells must be `y`, so cells seeing both can't.
+        fn w_wing(&self) -> Option<Step> {
+            let bivalue = self.bivalue_cells();
+            for (k, &a) in bivalue.iter().enumerate() {
+                for &b in &bivalue[k + 1..] {
+                    let digits = self.mask(a);
+                    if self.mask(b) != digits || self.sees(a, b) {
+                        continue;
+                    }
+                    for x in (1..=BOARD_SIZE as i8).filter(|&d| digits & digit_bit(d) != 0) {
+                        let y = digits & !digit_bit(x);
+                        for house in self.board.houses() {
+                            let [p, q] = self.places(house, x)[..] else {
+                                continue;
+                            };
+                            if [p, q].iter().any(|end| [a, b].contains(end)) {
+                                continue;
+                            }
+                            let linked = (self.sees(p, a) && self.sees(q, b))
+                                || (self.sees(p, b) && self.sees(q, a));
+                            if !linked {
+                                continue;
+                            }
+                            let eliminations = self.eliminate_seen_by(&[a, b], y);
+                            if !eliminations.is_empty() {
+                                let mut step = Step::new(Technique::WWing);
+                                step.eliminations = eliminations;
+                                step.cells = vec![a, b, p, q];
+                                step.houses.push(house.name.clone());
+                                return Some(step);
+                            }
+                        }
+                    }
+                }
+            }
+            return None;
+        }
+
+        /// Whether swapping two digits between the cells of a rectangle keeps every rule:
+        /// each house holds none or two of the cells, and there are no variant rules
+        fn is_rectangle(&self, cells: &[(usize, usize); 4]) -> bool {
+            if self.board.variant_constraints().next().is_some() {
+                return false;
+            }
+            return self.board.houses().iter().all(|house| {
+                let inside = cells.iter().filter(|&&cell| house.contains(cell)).count();
+                inside == 0 || inside == 2
+            });
+        }
+
+        fn unique_rectangle(&self, technique: Technique) -> Option<Step> {
+            for r1 in 0..BOARD_SIZE {
+                for r2 in r1 + 1..BOARD_SIZE {
+                    for c1 in 0..BOARD_SIZE {
+                        for c2 in c1 + 1..BOARD_SIZE {
+                            let cells = [(r1, c1), (r1, c2), (r2, c1), (r2, c2)];
+                            if !cells.iter().all(|&cell| self.is_empty(cell))
+                                || !self.is_rectangle(&cells)
+                            {
+                                continue;
+                            }
+                            let common =
+                                cells.iter().fold(ALL_DIGITS, |all, &c| all & self.mask(c));
+                            for pair in subsets(2).filter(|&pair| pair & !common == 0) {
+                                let step = self.rectangle_step(technique, &cells, pair);
+                                if step.is_some() {
+                                    return step;
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            return None;
+        }
+
+        /// The eliminations of a rectangle whose cells all hold the two digits of `pair`.
+        /// The floor is the cells with nothing else, the roof the cells with more.
+        fn rectangle_step(
+            &self,
+            technique: Technique,
+            cells: &[(usize, usize); 4],
+            pair: u16,
+        ) -> Option<Step> {
+            let roof: Vec<(usize, usize)> = cells
+                .iter()
+                .copied()
+                .filter(|&cell| self.mask(cell) != pair)
+                .collect();
+            let mut step = Step::new(technique);
+            step.cells = cells.to_vec();
+            if technique == Technique::UniqueRectangle1 {
+                let [(i, j)] = roof[..] else {
+                    return None;
+                };
+                step.eliminations = self
+                    .candidates
+                    .digits(i, j)
+                    .filter(|&d| pair & digit_bit(d) != 0)
+                    .map(|d| (i, j, d))
+                    .collect();
+                return Some(step);
+            }
+            // The roof must be a side of the rectangle
+            let [a, b] = roof[..] else {
+                return None;
+            };
+            if a.0 != b.0 && a.1 != b.1 {
+                return None;
+            }
+            let extra = (self.mask(a) | self.mask(b)) & !pair;
+            let houses = self
+                .board
+                .houses()
+                .iter()
+                .filter(|house| house.contains(a) && house.contains(b));
+            match technique {
+                Technique::UniqueRectangle2 => {
+                    if extra.count_ones() != 1 || self.mask(a) != self.mask(b) {
+                        return None;
+                    }
+                    step.eliminations = self.eliminate_seen_by(&[a, b], extra);
+                }
+                Technique::UniqueRectangle3 => {
+                    // The roof acts as one cell holding the extra digits
+                    for house in houses {
+                        let others: Vec<(usize, usize)> = house
+                            .cells
+                            .iter()
+                            .copied()
+                            .filter(|&cell| self.is_empty(cell) && cell != a && cell != b)
+                            .collect();
+                        for size in 1..=3 {
+                            for pick in subsets(size) {
+                                let subset = picked(pick, &others);
+                                let digits = subset
+                                    .iter()
+                                    .fold(extra, |all, &cell| all | self.mask(cell));
+                                if subset.len() != size as usize || digits.count_ones() != size + 1
+                                {
+                                    continue;
+                                }
+                                for &(i, j) in others.iter().filter(|cell| !subset.contains(cell)) {
+                                    for digit in self.candidates.digits(i, j) {
+                                        if digits & digit_bit(digit) != 0 {
+                                            step.eliminations.push((i, j, digit));
+                                        }
+                                    }
+                                }
+                                if !step.eliminations.is_empty() {
+                                    step.cells.extend(subset);
+                                    step.houses.push(house.name.clone());
+                                    return Some(step);
+                                }
+                            }
+                        }
+                    }
+                }
+                _ => {
+                    // One of the pair has to go in the roof, so the other can't
+                    for house in houses {
+                        for digit in (1..=BOARD_SIZE as i8).filter(|&d| pair & digit_bit(d) != 0) {
+                            if self.places(house, digit).len() != 2 {
+                                continue;
+                            }
+                            let other = pair & !digit_bit(digit);
+                            step.eliminations = [a, b]
+                                .iter()
+                                .flat_map(|&(i, j)| {
+                                    self.candidates
+                                        .digits(i, j)
+                                        .filter(|&d| digit_bit(d) == other)
+                                        .map(move |d| (i, j, d))
+                                })
+                                .collect();
+                            if !step.eliminations.is_empty() {
+                                step.houses.push(house.name.clone());
+                                return Some(step);
+                            }
+                        }
+                    }
+                }
+            }
+            return (!step.eliminations.is_empty()).then_some(step);
+        }
+
+        /// With every empty cell but one down to two candidates, and each candidate in two
+        /// places of every house but one digit of that cell in three, the board would have
+        /// no solution or several unless the cell takes that digit
+        fn bug_plus_one(&self) -> Option<Step> {
+            if self.board.variant_constraints().next().is_some() {
+                return None;
+            }
+            let [cell] = self.cells_with(3)[..] else {
+                return None;
+            };
+            let empty = (0..BOARD_SIZE * BOARD_SIZE)
+                .filter(|&k| self.is_empty((k / BOARD_SIZE, k % BOARD_SIZE)))
+                .count();
+            if self.bivalue_cells().len() + 1 != empty {
+                return None;
+            }
+            for digit in self.candidates.digits(cell.0, cell.1) {
+                let fits = self.board.houses().iter().all(|house| {
+                    (1..=BOARD_SIZE as i8).all(|d| {
+                        let places = self.places(house, d).len();
+                        let extra = d == digit && house.contains(cell);
+                        places == 0 || places == if extra { 3 } else { 2 }
+                    })
+                });
+                if fits {
+                    let mut step = Step::new(Technique::BugPlusOne);
+                    step.placement = Some((cell.0, cell.1, digit));
+                    return Some(step);
+                }
+            }
+            return None;
+        }
+
+        /// The step SE would take, with its difficulty in tenths
+        fn se_step(&self, options: &LogicOptions) -> Option<(Step, u32)> {
+            let houses = self.board.houses();
+            let direct = |step: &Step| self.is_direct(step);
+            let singles: [(u32, &dyn Fn() -> Option<Step>); 7] = [
+                (12, &|| self.hidden_single(&houses[LINES..])),
+                (15, &|| self.hidden_single(&houses[..LINES])),
+                (17, &|| self.locked_candidates(Technique::Pointing, &direct)),
+                (19, &|| self.locked_candidates(Technique::Claiming, &direct)),
+                (20, &|| {
+                    self.hidden_subset(Technique::HiddenPair, 2, &direct)
+                }),
+                (23, &|| self.naked_single()),
+                (25, &|| {
+                    self.hidden_subset(Technique::HiddenTriple, 3, &direct)
+                }),
+            ];
+            for (tenths, search) in singles {
+                if let Some(step) = search() {
+                    return Some((step, tenths));
+                }
+            }
+
+            // Chains are rated by their length, so the easiest one may come from any of them
+            let mut best: Option<(Step, u32)> = None;
+            let chains = [
+                Technique::XYChain,
+                Technique::CellForcingChain,
+                Technique::HouseForcingChain,
+            ];
+            let all = SE_DIFFICULTIES.into_iter().chain(
+                chains
+                    .into_iter()
+                    .map(|technique| (technique, se_chain_base(technique, &[]))),
+            );
+            for (technique, base) in all {
+                if best.as_ref().is_some_and(|(_, tenths)| *tenths <= base) {
+                    break;
+                }
+                let Some(step) = self.find(technique, options) else {
+                    continue;
+                };
+                let tenths = if technique == Technique::UniqueRectangle3 {
+                    base + step.cells.len() as u32 - 4
+                } else if step.chains.is_empty() {
+                    base
+                } else {
+                    let nodes = step.chains.iter().map(|chain| chain.nodes.len()).sum();
+                    se_chain_base(technique, &step.chains) + se_length_difficulty(nodes)
+                };
+                if best.as_ref().is_none_or(|(_, best)| tenths < *best) {
+                    best = Some((step, tenths));
+                }
+            }
+            return best;
+        }
+
+        /// Whether a step leaves a digit with one place in a house, which SE rates as a
+        /// direct version of the technique
+        fn is_direct(&self, step: &Step) -> bool {
+            return step.eliminations.iter().any(|&(i, j, digit)| {
+                self.board
+                    .houses()
+                    .iter()
+                    .filter(|house| house.contains((i, j)))
+                    .any(|house| {
+                        let left = self
+                            .places(house, digit)
+                            .into_iter()
+                            .filter(|&(r, c)| !step.eliminations.contains(&(r, c, digit)));
+                        left.count() == 1
+                    })
+            });
+        }
+
+        /// Links between the candidates of empty cells that `keep` accepts, called with
+        /// whether the link is strong and the two nodes
+        fn links(&self, keep: impl Fn(bool, usize, usize) -> bool) -> Links {
+            let mut strong = vec![Vec::new(); NODES];
+            let mut weak = vec![Vec::new(); NODES];
+            let mut join = |group: &[usize]| {
+                for &a in group {
+                    for &b in group.iter().filter(|&&b| b != a) {
+                        if group.len() == 2 && keep(true, a, b) {
+                            strong[a].push(b);
+                        }
+                        if keep(false, a, b) {
+                            weak[a].push(b);
+                        }
+                    }
+                }
+            };
+            for i in 0..BOARD_SIZE {
+                for j in 0..BOARD_SIZE {
+                    let nodes: Vec<usize> = self
+                        .candidates
+                        .digits(i, j)
+                        .map(|digit| node((i, j), digit))
+                        .collect();
+                    join(&nodes);
+                }
+            }
+            for house in self.board.houses() {
+                for digit in 1..=BOARD_SIZE as i8 {
+                    let nodes: Vec<usize> = self
+                        .places(house, digit)
+                        .into_iter()
+                        .map(|cell| node(cell, digit))
+                        .collect();
+                    join(&nodes);
+                }
+            }
+            for list in strong.iter_mut().chain(weak.iter_mut()) {
+                list.sort_unstable();
+                list.dedup();
+            }
+            return Links { strong, weak };
+        }
+
+        /// The shortest chain along `links` with eliminations, which are the candidates
+        /// weakly linked to both ends. XY-Chains must also end on the digit they start on.
+        fn chain(&self, technique: Technique, links: &Links, cap: usize) -> Option<Step> {
+            let weak = &self.links(|_, _, _| true).weak;
+            let mut best: Option<Step> = None;
+            for start in (0..NODES).filter(|&start| !links.strong[start].is_empty()) {
+                let walk = Walk::new(links, start, false, cap);
+                for &state in &walk.order {
+                    let end = state / 2;
+                    let shorter = best
+                        .as_ref()
+                        .is_none_or(|best| walk.depth[state] + 1 < best.chains[0].nodes.len());
+                    if !shorter {
+                        break;
+                    }
+                    if state % 2 == 0 || end == start {
+                        continue;
+                    }
+                    if technique == Technique::XYChain && end % 9 != start % 9 {
+                        continue;
+                    }
+                    let eliminations: Vec<(usize, usize, i8)> = weak[start]
+                        .iter()
+                        .filter(|&node| *node != end && weak[end].binary_search(node).is_ok())
+                        .map(|&node| candidate(node))
+                        .collect();
+                    if !eliminations.is_empty() {
+                        let mut step = Step::new(technique);
+                        step.eliminations = eliminations;
+                        step.chains.push(walk.chain(state, true));
+                        best = Some(step);
+                        break;
+                    }
+                }
+            }
+            return best;
+        }
+
+        /// The shortest chain from a candidate back to itself. Loops made only of alternating
+        /// links lead to the same eliminations as an AIC, so those aren't looked for.
+        fn nice_loop(&self, cap: usize) -> Option<Step> {
+            let links = self.links(|_, _, _| true);
+            let mut best: Option<(usize, Step)> = None;
+            for start in (0..NODES).filter(|&start| !links.weak[start].is_empty()) {
+                for on in [false, true] {
+                    let walk = Walk::new(&links, start, on, cap);
+                    let state = 2 * start + usize::from(!on);
+                    let depth = walk.depth[state];
+                    if depth == usize::MAX || best.as_ref().is_some_and(|(best, _)| depth >= *best)
+                    {
+                        continue;
+                    }
+                    let mut step = Step::new(Technique::NiceLoop);
+                    if on {
+                        step.eliminations.push(candidate(start));
+                    } else {
+                        step.placement = Some(candidate(start));
+                    }
+                    step.chains.push(walk.chain(state, !on));
+                    best = Some((depth, step));
+                }
+            }
+            return best.map(|(_, step)| step);
+        }
+
+        /// Follows chains from each candidate of a cell, or from each place of a digit in a
+        /// house, and keeps the shortest conclusion they all share. Only cells and houses
+        /// with two or three choices are tried.
+        fn forcing_chain(&self, technique: Technique, cap: usize) -> Option<Step> {
+            let links = self.links(|_, _, _| true);
+            let mut groups: Vec<(Vec<usize>, Step)> = Vec::new();
+            if technique == Technique::CellForcingChain {
+                for cell in self.cells_with(2).into_iter().chain(self.cells_with(3)) {
+                    let nodes = self.candidates.digits(cell.0, cell.1);
+                    let mut step = Step::new(technique);
+                    step.cells.push(cell);
+                    groups.push((nodes.map(|digit| node(cell, digit)).collect(), step));
+                }
+            } else {
+                for house in self.board.houses() {
+                    for digit in 1..=BOARD_SIZE as i8 {
+                        let places = self.places(house, digit);
+                        if (2..=3).contains(&places.len()) {
+                            let mut step = Step::new(technique);
+                            step.houses.push(house.name.clone());
+                            step.cells = places.clone();
+                            groups.push((
+                                places.iter().map(|&cell| node(cell, digit)).collect(),
+                                step,
+                            ));
+                        }
+                    }
+                }
+            }
+
+            let mut walks: Vec<Option<Walk>> = (0..NODES).map(|_| None).collect();
+            let mut best: Option<(usize, Step)> = None;
+            for (nodes, mut step) in groups {
+                for &start in &nodes {
+                    if walks[start].is_none() {
+                        walks[start] = Some(Walk::new(&links, start, true, cap));
+                    }
+                }
+                let branches: Vec<&Walk> = nodes
+                    .iter()
+                    .filter_map(|&start| walks[start].as_ref())
+                    .collect();
+                let mut conclusion: Option<(usize, usize)> = None;
+                for state in 0..2 * NODES {
+                    if branches.iter().any(|walk| walk.depth[state] == usize::MAX) {
+                        continue;
+                    }
+                    let length = branches.iter().map(|walk| walk.depth[state]).sum();
+                    if conclusion.is_none_or(|(shortest, _)| length < shortest) {
+                        conclusion = Some((length, state));
+                    }
+                }
+                let Some((length, state)) = conclusion else {
+                    continue;
+                };
+                if best
+                    .as_ref()
+                    .is_some_and(|(shortest, _)| length >= *shortest)
+                {
+                    continue;
+                }
+                if state % 2 == 1 {
+                    step.placement = Some(candidate(state / 2));
+                } else {
+                    step.eliminations.push(candidate(state / 2));
+                }
+                step.chains = branches
+                    .iter()
+                    .map(|walk| walk.chain(state, false))
+                    .collect();
+                best = Some((length, step));
+            }
+            return best.map(|(_, step)| step);
+        }
+    }
+
+    /// SE's difficulty for a chain before its length is counted, in tenths. Chains on one
+    /// digit are forcing X-chains, other single chains forcing chains, and chains with
+    /// several branches multiple forcing chains.
+    fn se_chain_base(technique: Technique, chains: &[Chain]) -> u32 {
+        if matches!(
+            technique,
+            Technique::CellForcingChain | Technique::HouseForcingChain
+        ) {
+            return 82;
+        }
+        let digit = chains
+            .first()
+            .and_then(|chain| chain.nodes.first())
+            .map(|node| node.2);
+        let one_digit = chains
+            .iter()
+            .flat_map(|chain| &chain.nodes)
+            .all(|node| Some(node.2) == digit);
+        if technique == Technique::XChain || (one_digit && !chains.is_empty()) {
+            return 66;
+        }
+        return 70;
+    }
+
+    /// SE's extra tenths for long chains: one more each time the length passes 4, 6, 8, 12,
+    /// 16, 24 and so on
+    fn se_length_difficulty(nodes: usize) -> u32 {
+        let length = nodes.saturating_sub(2);
+        let mut ceiling = 4;
+        let mut tenths = 0;
+        while length > ceiling {
+            ceiling = if tenths % 2 == 0 {
+                ceiling * 3 / 2
+            } else {
+                ceiling * 4 / 3
+            };
+            tenths += 1;
+        }
+        return tenths;
+    }
+
+    /// Candidates, as nodes `(row * 9 + col) * 9 + digit - 1`
+    const NODES: usize = BOARD_SIZE * BOARD_SIZE * BOARD_SIZE;
+
+    fn node((i, j): (usize, usize), digit: i8) -> usize {
+        return (i * BOARD_SIZE + j) * BOARD_SIZE + digit as usize - 1;
+    }
+
+    fn cell_of(node: usize) -> (usize, usize) {
+        let cell = node / BOARD_SIZE;
+        return (cell / BOARD_SIZE, cell % BOARD_SIZE);
+    }
+
+    fn candidate(node: usize) -> (usize, usize, i8) {
+        let (i, j) = cell_of(node);
+        return (i, j, (node % BOARD_SIZE) as i8 + 1);
+    }
+
+    /// Strong links (one of two candidates is true) and weak links (at most one is), as
+    /// lists of linked nodes
+    struct Links {
+        strong: Vec<Vec<usize>>,
+        weak: Vec<Vec<usize>>,
+    }
+
+    /// Everything that follows from a candidate being true or false. States are
+    /// `node * 2 + 1` for a true candidate and `node * 2` for a false one: a false candidate
+    /// makes its strong links true, and a true one makes its weak links false.
+    struct Walk {
+        /// Links from the start to each state, `usize::MAX` where it isn't reached
+        depth: Vec<usize>,
+        previous: Vec<usize>,
+        /// Reached states, nearest first
+        order: Vec<usize>,
+    }
+
+    impl Walk {
+        fn new(links: &Links, start: usize, on: bool, cap: usize) -> Self {
+            let first = 2 * start + usize::from(on);
+            let mut walk = Walk {
+                depth: vec![usize::MAX; 2 * NODES],
+                previous: vec![usize::MAX; 2 * NODES],
+                order: vec![first],
+            };
+            walk.depth[first] = 0;
+            let mut next = 0;
+            while let Some(&state) = walk.order.get(next) {
+                next += 1;
+                if walk.depth[state] == cap {
+                    continue;
+                }
+                let (linked, on) = if state % 2 == 1 {
+                    (&links.weak[state / 2], false)
+                } else {
+                    (&links.strong[state / 2], true)
+                };
+                for &other in linked {
+                    let reached = 2 * other + usize::from(on);
+                    if walk.depth[reached] == usize::MAX {
+                        walk.depth[reached] = walk.depth[state] + 1;
+                        walk.previous[reached] = state;
+                        walk.order.push(reached);
+                    }
+                }
+            }
+            return walk;
+        }
+
+        /// The chain from the start to a reached state
+        fn chain(&self, state: usize, strong_first: bool) -> Chain {
+            let mut nodes = vec![candidate(state / 2)];
+            let mut at = state;
+            while self.depth[at] > 0 {
+                at = self.previous[at];
+                nodes.push(candidate(at / 2));
+            }
+            nodes.reverse();
+            return Chain {
+                nodes,
+                strong_first,
+            };
+        }
+    }
+
+    /// A fish on one digit: base lines as bits, and the positions along them it covers
+    struct Fish {
+        digit: i8,
+        /// Base lines are columns and cover positions rows
+        by_columns: bool,
+        base: u16,
+        cover: u16,
+    }
+
+    /// Whether a fish technique is finned, and whether it is sashimi
+    fn fish_kind(technique: Technique) -> (bool, bool) {
+        return match technique {
+            Technique::XWing | Technique::Swordfish | Technique::Jellyfish => (false, false),
+            Technique::SashimiXWing | Technique::SashimiSwordfish | Technique::SashimiJellyfish => {
+                (true, true)
+            }
+            _ => (true, false),
+        };
+    }
+
+    fn fish_cell(by_columns: bool, line: usize, pos: usize) -> (usize, usize) {
+        return if by_columns { (pos, line) } else { (line, pos) };
+    }
+
+    /// Every way to pick `size` of nine things, as bitmasks
+    fn subsets(size: u32) -> impl Iterator<Item = u16> {
+        return (0..1u16 << BOARD_SIZE).filter(move |pick| pick.count_ones() == size);
+    }
+
+    fn picked<T: Copy>(pick: u16, items: &[T]) -> Vec<T> {
+        return items
+            .iter()
+            .enumerate()
+            .filter(|(k, _)| pick & (1 << k) != 0)
+            .map(|(_, &item)| item)
+            .collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::exact::exact::solve;
+    use crate::logic::logic::{
+        LogicOptions, Technique, Trace, se_rating, solve_logically, solve_logically_with,
+    };
+    use crate::rules::rules::apply_rules;
+    use crate::solver::solver::test_puzzles;
+    use crate::sudoku::sudoku::Board;
+
+    /// Needs locked candidates, a hidden triple and a naked quad
+    const SUBSETS: &str =
+        "070000058004020000020610000010900000800760300050000800000400000003000000002003507";
+
+    /// Puzzles with the SE-scale ratings this solver gave them, see the notes in the file
+    const SE_RATINGS: &str = include_str!("se_ratings.txt");
+
+    /// Checks every placement and elimination against the real solution
+    fn check_trace(board: &Board, trace: &Trace) {
+        let solved = solve(board).unwrap();
+        for step in &trace.steps {
+            if let Some((i, j, digit)) = step.placement {
+                assert_eq!(solved.get(i, j), Some(digit), "{}", step);
+            }
+            for &(i, j, digit) in &step.eliminations {
+                assert_ne!(solved.get(i, j), Some(digit), "{}", step);
+            }
+        }
+    }
+
+    #[test]
+    fn solves_with_a_trace() {
+        let board = Board::from_string(
+            "278000401609100050005006900430809000706003000091000800000020173860001004107934685",
+        )
+        .unwrap();
+        let trace = solve_logically(&board).unwrap();
+        assert!(trace.is_solved());
+        assert_eq!(trace.steps.len(), 81 - board.clue_count());
+        // r3c6 keeps the 6 out of the rest of row 1
+        assert_eq!(
+            trace.steps[0].to_string(),
+            "Hidden single in row 1 (r1c8 r3c6): r1c8=6"
+        );
+        for step in &trace.steps {
+            let (i, j, _) = step.placement.unwrap();
+            assert_eq!(step.cells[0], (i, j), "{}", step);
+        }
+        check_trace(&board, &trace);
+
+        let board = Board::from_string(SUBSETS).unwrap();
+        let trace = solve_logically(&board).unwrap();
+        assert!(trace.is_solved());
+        for technique in [
+            Technique::Pointing,
+            Technique::HiddenTriple,
+            Technique::NakedQuad,
+        ] {
+            assert!(trace.steps.iter().any(|step| step.technique == technique));
+        }
+        check_trace(&board, &trace);
+    }
+
+    #[test]
+    fn shows_cells_of_variant_rules() {
+        let mut board = Board::default();
+        apply_rules(&mut board, ["thermo r1c1 r1c2 r1c3"]).unwrap();
+        let options = LogicOptions {
+            techniques: vec![Technique::VariantRule],
+            ..LogicOptions::default()
+        };
+        let trace = solve_logically_with(&board, &options).unwrap();
+        let step = &trace.steps[0];
+        assert_eq!(step.technique, Technique::VariantRule);
+        assert_eq!(step.cells, [(0, 0), (0, 1), (0, 2)]);
+    }
+
+    #[test]
+    fn solves_bundled_puzzles() {
+        for line in test_puzzles() {
+            let board = Board::from_string(line).unwrap();
+            let trace = solve_logically(&board).unwrap();
+            assert!(trace.is_solved(), "{}", line);
+            check_trace(&board, &trace);
+        }
+    }
+
+    #[test]
+    fn reports_stalls() {
+        // Easter Monster, far beyond these techniques
+        let board = Board::from_string(
+            "100000002090400050006000700050903000000070000000085040700000600030009080002000001",
+        )
+        .unwrap();
+        let trace = solve_logically(&board).unwrap();
+        assert!(!trace.is_solved());
+        assert!(trace.candidates.count(0, 1) > 1);
+
+        let options = LogicOptions {
+            techniques: vec![Technique::HiddenSingle, Technique::NakedSingle],
+            ..LogicOptions::default()
+        };
+        let board = Board::from_string(SUBSETS).unwrap();
+        assert!(!solve_logically_with(&board, &options).unwrap().is_solved());
+
+        // Nothing is left for r1c9
+        let broken = Board::from_string(
+            "123456780000000009000000000000000000000000000000000000000000000000000000000000000",
+        )
+        .unwrap();
+        assert!(solve_logically(&broken).is_err());
+    }
+
+    #[test]
+    fn finds_fish_and_wings() {
+        let puzzles = [
+            (
+                "700190200300000500010050000100000700006803000000000030000900006000018095004020080",
+                Technique::XWing,
+            ),
+            (
+                "006001840040050020000040070000003002008000050000200400004000038350700060900500000",
+                Technique::Swordfish,
+            ),
+            (
+                "700400010000860050610005000008004006000100009004080007301007000000030900490000000",
+                Technique::FinnedXWing,
+            ),
+            (
+                "070430060000000007006000590008069002000501000600000050009000005000080040400305600",
+                Technique::SashimiXWing,
+            ),
+            (
+                "870001500000400092060090000000000000000050048004209700050100060000002009700060080",
+                Technique::FinnedSwordfish,
+            ),
+            (
+                "870001500000400092060090000000000000000050048004209700050100060000002009700060080",
+                Technique::XYWing,
+            ),
+            (
+                "008900005050701000002000008000007006203004900800020070506000000030000009400205000",
+                Technique::XYZWing,
+            ),
+            (
+                "008009100003000007092300800020400001000003020800000000040900002000014000100050780",
+                Technique::WWing,
+            ),
+        ];
+        for (puzzle, technique) in puzzles {
+            let board = Board::from_string(puzzle).unwrap();
+            let trace = solve_logically(&board).unwrap();
+            assert!(trace.steps.iter().any(|step| step.technique == technique));
+            check_trace(&board, &trace);
+
+            // Each technique can be switched off on its own
+            let options = LogicOptions {
+                techniques: Technique::ALL
+                    .into_iter()
+                    .filter(|&other| other != technique)
+                    .collect(),
+                ..LogicOptions::default()
+            };
+            let trace = solve_logically_with(&board, &options).unwrap();
+            assert!(trace.steps.iter().all(|step| step.technique != technique));
+            check_trace(&board, &trace);
+        }
+
+        assert_eq!("xy-wing".parse::<Technique>().unwrap(), Technique::XYWing);
+        assert_eq!(
+            "sashimi-swordfish".parse::<Technique>().unwrap(),
+            Technique::SashimiSwordfish
+        );
+        assert!("y-wing".parse::<Technique>().is_err());
+    }
+
+    #[test]
+    fn finds_chains() {
+        let puzzles = [
+            (
+                "310000407000050000078003000000572800900000001000006200809360000000010000002000074",
+                Technique::XChain,
+            ),
+            (
+                "001000700070030052900070000003728004000000005000040600009000000002009081000082030",
+                Technique::XYChain,
+            ),
+            (
+                "010000709003000050090070020500000000040000008800032040001008004300090500000520001",
+                Technique::AIC,
+            ),
+            (
+                "000403010920860050040000002000005000060082400104000009006000000000090100009034206",
+                Technique::CellForcingChain,
+            ),
+            (
+                "280000040006000001070200938060080000000050000000006405007000014003000000020140080",
+                Technique::HouseForcingChain,
+            ),
+        ];
+        for (puzzle, technique) in puzzles {
+            let board = Board::from_string(puzzle).unwrap();
+            let trace = solve_logically(&board).unwrap();
+            assert!(trace.is_solved());
+            let step = trace.steps.iter().find(|step| step.technique == technique);
+            assert!(!step.unwrap().chains.is_empty(), "{}", puzzle);
+            check_trace(&board, &trace);
+        }
+
+        // Without AIC the same eliminations come from nice loops
+        let board = Board::from_string(puzzles[2].0).unwrap();
+        let options = LogicOptions {
+            techniques: Technique::ALL
+                .into_iter()
+                .filter(|&technique| technique != Technique::AIC)
+                .collect(),
+            max_chain_length: 8,
+            ..LogicOptions::default()
+        };
+        let trace = solve_logically_with(&board, &options).unwrap();
+        assert!(
+            trace
+                .steps
+                .iter()
+                .any(|step| step.technique == Technique::NiceLoop)
+        );
+        let chains = trace.steps.iter().flat_map(|step| &step.chains);
+        assert!(chains.clone().all(|chain| chain.nodes.len() <= 9));
+        check_trace(&board, &trace);
+    }
+
+    #[test]
+    fn uses_uniqueness_when_allowed() {
+        let puzzles = [
+            (
+                "280600000000004906001000008000007000000240007000300502100000003004538000570000400",
+                Technique::UniqueRectangle1,
+            ),
+            (
+                "000000040003008000050000361020400900030007000000900002708010000000040076100003050",
+                Technique::UniqueRectangle2,
+            ),
+            (
+                "009075000000400170800000300002903000080000050001000700040007810050008007000060000",
+                Technique::UniqueRectangle3,
+            ),
+            (
+                "000000504200308000000579030000000052069000003300860400100790000070206090000000005",
+                Technique::UniqueRectangle4,
+            ),
+            (
+                "000030040109007000000280670000040000300700000800009000710090035050008700000100400",
+                Technique::BugPlusOne,
+            ),
+        ];
+        let unique = LogicOptions {
+            unique: true,
+            ..LogicOptions::default()
+        };
+        for (puzzle, technique) in puzzles {
+            let board = Board::from_string(puzzle).unwrap();
+            let trace = solve_logically_with(&board, &unique).unwrap();
+            assert!(trace.steps.iter().any(|step| step.technique == technique));
+            check_trace(&board, &trace);
+
+            let trace = solve_logically(&board).unwrap();
+            assert!(
+                trace
+                    .steps
+                    .iter()
+                    .all(|step| !step.technique.needs_uniqueness())
+            );
+        }
+    }
+
+    #[test]
+    fn keeps_se_scale_ratings() {
+        let lines = SE_RATINGS
+            .lines()
+            .filter(|l| !l.is_empty() && !l.starts_with('#'));
+        for line in lines {
+            let (puzzle, rating) = line.split_once(' ').unwrap();
+            let rated = se_rating(&Board::from_string(puzzle).unwrap()).unwrap();
+            assert!(rated.complete);
+            assert_eq!(rated.rating, rating.parse::<f64>().unwrap(), "{}", puzzle);
+        }
+
+        // Needs a short X-chain, which SE calls a forcing X-chain
+        let board = Board::from_string(
+            "310000407000050000078003000000572800900000001000006200809360000000010000002000074",
+        )
+        .unwrap();
+        let rated = se_rating(&board).unwrap();
+        assert!(rated.complete);
+        assert_eq!(rated.rating, 6.6);
+
+        // Unique rectangles would be wrong here
+        let board = Board::from_string(
+            "000000042340100075900060800000908004000520000009000000080002007100600200027000000",
+        )
+        .unwrap();
+        se_rating(&board).unwrap_err();
+    }
+}
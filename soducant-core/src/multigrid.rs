@@ -0,0 +1,564 @@
+pub mod multigrid {
+    use std::fmt::{Display, Formatter};
+    use std::str::FromStr;
+
+    use crate::constraint::constraint::{ALL_DIGITS, Constraint, Grid, digit_bit};
+    use crate::sudoku::sudoku::{BOARD_SIZE, Board};
+
+    /// Top-left cells of the five grids of a samurai: the corners, then the centre grid whose
+    /// corner boxes they share
+    pub const SAMURAI: [(usize, usize); 5] = [(0, 0), (0, 12), (6, 6), (12, 0), (12, 12)];
+
+    /// Several 9x9 boards laid out on one larger sheet, sharing the cells where they overlap.
+    /// Each board keeps its own variant rules.
+    #[derive(Debug, Clone)]
+    pub struct MultiBoard {
+        /// Boards and the sheet cell of their top-left corner
+        grids: Vec<(Board, (usize, usize))>,
+        rows: usize,
+        cols: usize,
+    }
+
+    impl MultiBoard {
+        /// Lays out boards with their top-left corners at the given sheet cells, which must
+        /// line up with the 3x3 boxes. Givens on shared cells have to agree; a given on one board
+        /// is copied to every board sharing the cell.
+        pub fn new(
+            grids: Vec<(Board, (usize, usize))>,
+        ) -> Result<Self, Box<dyn std::error::Error>> {
+            if grids.is_empty() {
+                return Err(Box::from("Multi-grid puzzle has no grids"));
+            }
+            if let Some((_, (row, col))) = grids.iter().find(|(_, (r, c))| r % 3 != 0 || c % 3 != 0)
+            {
+                return Err(Box::from(format!(
+                    "Grid at ({},{}) doesn't line up with the boxes",
+                    row, col
+                )));
+            }
+            let rows = grids
+                .iter()
+                .map(|(_, (r, _))| r + BOARD_SIZE)
+                .max()
+                .unwrap_or(0);
+            let cols = grids
+                .iter()
+                .map(|(_, (_, c))| c + BOARD_SIZE)
+                .max()
+                .unwrap_or(0);
+            let mut board = MultiBoard { grids, rows, cols };
+
+            for row in 0..rows {
+                for col in 0..cols {
+                    let mut value = None;
+                    for (grid, (i, j)) in board.covering(row, col) {
+                        match (value, grid.get(i, j)) {
+                            (Some(a), Some(b)) if a != b => {
+                                return Err(Box::from(format!(
+                                    "Grids disagree at ({},{}): {} and {}",
+                                    row, col, a, b
+                                )));
+                            }
+                            (None, Some(b)) => value = Some(b),
+                            _ => {}
+                        }
+                    }
+                    if value.is_some() {
+                        board.set(row, col, value);
+                    }
+                }
+            }
+            return Ok(board);
+        }
+
+        /// Five boards in the order of [`SAMURAI`]
+        pub fn samurai(boards: Vec<Board>) -> Result<Self, Box<dyn std::error::Error>> {
+            if boards.len() != SAMURAI.len() {
+                return Err(Box::from(format!(
+                    "Samurai needs 5 grids, got {}",
+                    boards.len()
+                )));
+            }
+            return MultiBoard::new(boards.into_iter().zip(SAMURAI).collect());
+        }
+
+        /// Reads a sheet drawn one line per row: digits for givens, `0` or `.` for empty cells
+        /// and anything else where no grid covers the sheet. `offsets` places the grids, whose
+        /// rules are the standard ones.
+        pub fn from_layout(
+            text: &str,
+            offsets: &[(usize, usize)],
+        ) -> Result<Self, Box<dyn std::error::Error>> {
+            let lines: Vec<Vec<char>> = text
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .map(|line| line.chars().collect())
+                .collect();
+            let mut grids = Vec::new();
+            for &(row, col) in offsets {
+                let mut compact = String::new();
+                for i in row..row + BOARD_SIZE {
+                    for j in col..col + BOARD_SIZE {
+                        let c = lines.get(i).and_then(|line| line.get(j)).copied();
+                        match c {
+                            Some('.' | '0') => compact.push('0'),
+                            Some(c @ '1'..='9') => compact.push(c),
+                            _ => {
+                                return Err(Box::from(format!(
+                                    "Layout has no cell at ({},{})",
+                                    i, j
+                                )));
+                            }
+                        }
+                    }
+                }
+                grids.push((Board::from_string(&compact)?, (row, col)));
+            }
+            return MultiBoard::new(grids);
+        }
+
+        pub fn grids(&self) -> impl Iterator<Item = (&Board, (usize, usize))> {
+            return self.grids.iter().map(|(board, offset)| (board, *offset));
+        }
+
+        /// Rows and columns of the sheet
+        pub fn size(&self) -> (usize, usize) {
+            return (self.rows, self.cols);
+        }
+
+        /// Boards covering a sheet cell, with the cell's position on each
+        fn covering(
+            &self,
+            row: usize,
+            col: usize,
+        ) -> impl Iterator<Item = (&Board, (usize, usize))> {
+            return self.grids.iter().filter_map(move |(board, (r, c))| {
+                let (i, j) = (row.checked_sub(*r)?, col.checked_sub(*c)?);
+                return (i < BOARD_SIZE && j < BOARD_SIZE).then_some((board, (i, j)));
+            });
+        }
+
+        /// Whether some board covers the sheet cell
+        pub fn contains(&self, row: usize, col: usize) -> bool {
+            return self.covering(row, col).next().is_some();
+        }
+
+        pub fn get(&self, row: usize, col: usize) -> Option<i8> {
+            return self
+                .covering(row, col)
+                .next()
+                .and_then(|(board, (i, j))| board.get(i, j));
+        }
+
+        /// Sets a sheet cell on every board that covers it
+        pub fn set(&mut self, row: usize, col: usize, value: Option<i8>) {
+            for (board, (r, c)) in &mut self.grids {
+                if (*r..*r + BOARD_SIZE).contains(&row) && (*c..*c + BOARD_SIZE).contains(&col) {
+                    board.set(row - *r, col - *c, value);
+                }
+            }
+        }
+
+        /// The sheet one line per row, `.` for empty cells and spaces where no grid is
+        pub fn to_layout_string(&self) -> String {
+            let mut out = String::new();
+            for row in 0..self.rows {
+                let line: String = (0..self.cols)
+                    .map(|col| match (self.contains(row, col), self.get(row, col)) {
+                        (false, _) => ' ',
+                        (true, None) => '.',
+                        (true, Some(num)) => (b'0' + num as u8) as char,
+                    })
+                    .collect();
+                out.push_str(line.trim_end());
+                out.push('\n');
+            }
+            return out;
+        }
+
+        /// Each board as 81 characters, one per line
+        pub fn to_clean_string(&self) -> String {
+            let lines: Vec<String> = self.grids().map(|(b, _)| b.to_clean_string()).collect();
+            return lines.join("\n");
+        }
+
+        fn has_box(&self, box_row: usize, box_col: usize) -> bool {
+            return self.contains(box_row * 3, box_col * 3);
+        }
+    }
+
+    impl FromStr for MultiBoard {
+        type Err = Box<dyn std::error::Error>;
+
+        /// A samurai, either as five lines of 81 characters in the order of [`SAMURAI`] or as
+        /// the 21-line layout
+        fn from_str(text: &str) -> Result<Self, Self::Err> {
+            let lines: Vec<&str> = text.lines().filter(|l| !l.trim().is_empty()).collect();
+            if lines.len() == SAMURAI.len() {
+                let boards = lines
+                    .iter()
+                    .map(|line| Board::from_string(line.trim()))
+                    .collect::<Result<Vec<_>, _>>()?;
+                return MultiBoard::samurai(boards);
+            }
+            return MultiBoard::from_layout(text, &SAMURAI);
+        }
+    }
+
+    /// Box-drawing character joining lines that go up, down, left and right
+    fn junction(up: bool, down: bool, left: bool, right: bool) -> char {
+        return match (up, down, left, right) {
+            (false, false, false, false) => ' ',
+            (true, true, false, false)
+            | (true, false, false, false)
+            | (false, true, false, false) => '│',
+            (false, false, true, true)
+            | (false, false, true, false)
+            | (false, false, false, true) => '─',
+            (false, true, false, true) => '┌',
+            (false, true, true, false) => '┐',
+            (true, false, false, true) => '└',
+            (true, false, true, false) => '┘',
+            (true, true, false, true) => '├',
+            (true, true, true, false) => '┤',
+            (false, true, true, true) => '┬',
+            (true, false, true, true) => '┴',
+            (true, true, true, true) => '┼',
+        };
+    }
+
+    impl Display for MultiBoard {
+        /// Draws the sheet with a border around every 3x3 box
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            let (box_rows, box_cols) = (self.rows / 3, self.cols / 3);
+            let has = |br: Option<usize>, bc: Option<usize>| match (br, bc) {
+                (Some(br), Some(bc)) if br < box_rows && bc < box_cols => self.has_box(br, bc),
+                _ => false,
+            };
+            for br in 0..=box_rows {
+                // Border above box row `br`
+                let mut line = String::new();
+                for bc in 0..=box_cols {
+                    let (above, below) = (br.checked_sub(1), Some(br));
+                    let (left, right) = (bc.checked_sub(1), Some(bc));
+                    let up = has(above, left) || has(above, right);
+                    let down = has(below, left) || has(below, right);
+                    let west = has(above, left) || has(below, left);
+                    let east = has(above, right) || has(below, right);
+                    line.push(junction(up, down, west, east));
+                    if bc < box_cols {
+                        line.push_str(if east {
+                            "───────"
+                        } else {
+                            "       "
+                        });
+                    }
+                }
+                writeln!(f, "{}", line.trim_end())?;
+                if br == box_rows {
+                    break;
+                }
+
+                for row in br * 3..br * 3 + 3 {
+                    let mut line = String::new();
+                    for bc in 0..=box_cols {
+                        let wall = has(Some(br), bc.checked_sub(1)) || has(Some(br), Some(bc));
+                        line.push(if wall { '│' } else { ' ' });
+                        if bc == box_cols {
+                            break;
+                        }
+                        for col in bc * 3..bc * 3 + 3 {
+                            line.push(' ');
+                            line.push(match self.get(row, col) {
+                                Some(num) => (b'0' + num as u8) as char,
+                                None => ' ',
+                            });
+                        }
+                        line.push(' ');
+                    }
+                    writeln!(f, "{}", line.trim_end())?;
+                }
+            }
+            Ok(())
+        }
+    }
+
+    /// A grid's top-left sheet cell and its variant rules
+    type GridRules<'a> = ((usize, usize), Vec<&'a dyn Constraint>);
+
+    /// Backtracking over every cell of the sheet at once, so shared cells always hold one digit
+    struct Search<'a> {
+        cols: usize,
+        /// Digit in each sheet cell, 0 when empty or not covered
+        cells: Vec<u8>,
+        /// Sheet cells covered by some grid
+        covered: Vec<usize>,
+        /// Every house of every grid, as sheet cells
+        houses: Vec<[usize; BOARD_SIZE]>,
+        house_masks: Vec<u16>,
+        houses_of: Vec<Vec<usize>>,
+        /// Variant rules of each grid, checked against the grid's view of the sheet
+        rules: Vec<GridRules<'a>>,
+    }
+
+    impl<'a> Search<'a> {
+        fn new(board: &'a MultiBoard) -> Result<Self, Box<dyn std::error::Error>> {
+            let size = board.rows * board.cols;
+            let mut search = Search {
+                cols: board.cols,
+                cells: vec![0; size],
+                covered: (0..size)
+                    .filter(|&cell| board.contains(cell / board.cols, cell % board.cols))
+                    .collect(),
+                houses: Vec::new(),
+                house_masks: Vec::new(),
+                houses_of: vec![Vec::new(); size],
+                rules: Vec::new(),
+            };
+            for (grid, (r, c)) in board.grids() {
+                for house in grid.houses() {
+                    let cells = house.cells.map(|(i, j)| (r + i) * board.cols + c + j);
+                    for &cell in &cells {
+                        search.houses_of[cell].push(search.houses.len());
+                    }
+                    search.houses.push(cells);
+                    search.house_masks.push(0);
+                }
+                let rules: Vec<&dyn Constraint> = grid.variant_constraints().collect();
+                if !rules.is_empty() {
+                    search.rules.push(((r, c), rules));
+                }
+            }
+
+            for &cell in &search.covered.clone() {
+                if let Some(num) = board.get(cell / board.cols, cell % board.cols) {
+                    if search.candidates(cell) & digit_bit(num) == 0 {
+                        return Err(Box::from(format!(
+                            "{} at ({},{}) conflicts with another given",
+                            num,
+                            cell / board.cols,
+                            cell % board.cols
+                        )));
+                    }
+                    search.place(cell, num as u8);
+                }
+            }
+            if search.breaks_rule() {
+                return Err(Box::from("Givens break a variant rule"));
+            }
+            return Ok(search);
+        }
+
+        fn candidates(&self, cell: usize) -> u16 {
+            let used = self.houses_of[cell]
+                .iter()
+                .fold(0, |used, &house| used | self.house_masks[house]);
+            return !used & ALL_DIGITS;
+        }
+
+        fn place(&mut self, cell: usize, digit: u8) {
+            self.cells[cell] = digit;
+            for &house in &self.houses_of[cell] {
+                self.house_masks[house] |= 1 << (digit - 1);
+            }
+        }
+
+        fn unplace(&mut self, cell: usize) {
+            let bit = !(1 << (self.cells[cell] - 1));
+            self.cells[cell] = 0;
+            for &house in &self.houses_of[cell] {
+                self.house_masks[house] &= bit;
+            }
+        }
+
+        /// One grid's part of the sheet
+        fn view(&self, (r, c): (usize, usize)) -> Grid {
+            return std::array::from_fn(|i| {
+                std::array::from_fn(|j| match self.cells[(r + i) * self.cols + c + j] {
+                    0 => None,
+                    digit => Some(digit as i8),
+                })
+            });
+        }
+
+        fn breaks_rule(&self) -> bool {
+            return self.rules.iter().any(|(offset, rules)| {
+                let view = self.view(*offset);
+                return rules.iter().any(|rule| rule.is_violated(&view));
+            });
+        }
+
+        /// Next cell to try and its candidates: a digit with one place left in a house, else
+        /// the empty cell with the fewest candidates. `None` once the sheet is full.
+        fn next_cell(&self) -> Option<(usize, u16)> {
+            for (house, cells) in self.houses.iter().enumerate() {
+                let missing = !self.house_masks[house] & ALL_DIGITS;
+                for digit in (1..=BOARD_SIZE as i8).filter(|&d| missing & digit_bit(d) != 0) {
+                    let mut places = cells.iter().filter(|&&cell| {
+                        self.cells[cell] == 0 && self.candidates(cell) & digit_bit(digit) != 0
+                    });
+                    match (places.next(), places.next()) {
+                        (None, _) => {
+                            let &cell = cells.iter().find(|&&cell| self.cells[cell] == 0)?;
+                            return Some((cell, 0));
+                        }
+                        (Some(&cell), None) => return Some((cell, digit_bit(digit))),
+                        _ => {}
+                    }
+                }
+            }
+
+            let mut best: Option<(usize, u16)> = None;
+            for &cell in &self.covered {
+                if self.cells[cell] != 0 {
+                    continue;
+                }
+                let candidates = self.candidates(cell);
+                if best.is_none_or(|(_, b)| candidates.count_ones() < b.count_ones()) {
+                    best = Some((cell, candidates));
+                    if candidates.count_ones() <= 1 {
+                        break;
+                    }
+                }
+            }
+            return best;
+        }
+
+        fn run(&mut self, limit: usize, found: &mut usize, first: &mut Option<Vec<u8>>) {
+            let Some((cell, candidates)) = self.next_cell() else {
+                *found += 1;
+                if first.is_none() {
+                    *first = Some(self.cells.clone());
+                }
+                return;
+            };
+            for digit in (1..=9u8).filter(|d| candidates & (1 << (d - 1)) != 0) {
+                self.place(cell, digit);
+                if !self.breaks_rule() {
+                    self.run(limit, found, first);
+                }
+                self.unplace(cell);
+                if *found >= limit {
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Finds a solution with every shared cell agreeing between its grids
+    pub fn solve(board: &MultiBoard) -> Result<MultiBoard, Box<dyn std::error::Error>> {
+        let mut search = Search::new(board)?;
+        let mut found = 0;
+        let mut first = None;
+        search.run(1, &mut found, &mut first);
+
+        let cells = first.ok_or("Puzzle has no solution")?;
+        let mut solved = board.clone();
+        for &cell in &search.covered {
+            solved.set(
+                cell / board.cols,
+                cell % board.cols,
+                Some(cells[cell] as i8),
+            );
+        }
+        return Ok(solved);
+    }
+
+    /// Counts solutions, stopping at `limit`. Boards with conflicting givens have none.
+    pub fn count_solutions(board: &MultiBoard, limit: usize) -> usize {
+        let Ok(mut search) = Search::new(board) else {
+            return 0;
+        };
+        let mut found = 0;
+        let mut first = None;
+        search.run(limit, &mut found, &mut first);
+        return found;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::multigrid::multigrid::{MultiBoard, count_solutions, solve};
+    use crate::sudoku::sudoku::{BOARD_SIZE, Board};
+
+    const PUZZLE: &str = "\
+...4.6..9   3..246.8.
+.5.78912.   ..25...34
+...123...   98...7.5.
+2.16...9.   ..375...8
+8..9.2..4   .2...8.41
+6..5...1.   ..64.3..2
+3172...48..3.6.82..93
+..2.9...1...2...95...
+9...41..24.8...6...2.
+      ..6.5.89.
+      3...8...6
+      4...167..
+.467...15...9...2.5.7
+.891...648...52..9.4.
+..7...8..2.5...4582..
+2.18...79   ...8...93
+..3.95...   43..1.6.2
+.95.1...6   869.3.4.5
+.12..8957   2...8.9.6
+.58..14.2   ...796...
+93...2...   7...4...1
+";
+
+    const SOLVED: &str = "\
+123456789   351246789
+456789123   672589134
+789123456   984137256
+231674895   413752968
+875912364   725968341
+694538217   896413572
+317265948123567821493
+542897631579248395617
+968341572468139674825
+      126754893
+      357982416
+      489316725
+346789215637984123567
+589123764891352679148
+127456893245671458239
+261834579   125864793
+473695128   437915682
+895217346   869237415
+612348957   243581976
+758961432   518796324
+934572681   796342851
+";
+
+    #[test]
+    fn reads_and_writes_samurai() {
+        let board: MultiBoard = PUZZLE.parse().unwrap();
+        assert_eq!(board.size(), (21, 21));
+        assert_eq!(board.to_layout_string(), PUZZLE);
+        assert!(!board.contains(0, 10));
+        // Shared with the centre grid
+        assert_eq!(board.get(6, 8), Some(8));
+
+        let by_grid: MultiBoard = board.to_clean_string().parse().unwrap();
+        assert_eq!(by_grid.to_layout_string(), PUZZLE);
+
+        let mut top_left = Board::default();
+        top_left.set(8, 8, Some(1));
+        let mut centre = Board::default();
+        centre.set(2, 2, Some(2));
+        let clash = MultiBoard::new(vec![(top_left, (0, 0)), (centre, (6, 6))]);
+        assert!(clash.is_err());
+        assert!(MultiBoard::new(vec![(Board::default(), (0, 4))]).is_err());
+    }
+
+    #[test]
+    fn solves_samurai() {
+        let board: MultiBoard = PUZZLE.parse().unwrap();
+        assert_eq!(count_solutions(&board, 2), 1);
+
+        let solved = solve(&board).unwrap();
+        assert_eq!(solved.to_layout_string(), SOLVED);
+        for (grid, _) in solved.grids() {
+            assert_eq!(grid.clue_count(), BOARD_SIZE * BOARD_SIZE);
+        }
+    }
+}
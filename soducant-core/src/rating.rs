@@ -0,0 +1,63 @@
+pub mod rating {
+    use std::fmt::{Display, Formatter};
+
+    use crate::exact::exact::{count_solutions, solve_with_stats};
+    use crate::logic::logic::se_rating;
+    use crate::sudoku::sudoku::Board;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+    pub enum Difficulty {
+        Easy,
+        Medium,
+        Hard,
+        Expert,
+    }
+
+    impl Display for Difficulty {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            let name = match self {
+                Difficulty::Easy => "easy",
+                Difficulty::Medium => "medium",
+                Difficulty::Hard => "hard",
+                Difficulty::Expert => "expert",
+            };
+            write!(f, "{}", name)
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct Rating {
+        pub difficulty: Difficulty,
+        pub clues: usize,
+        /// Guesses the backtracking solver needed
+        pub guesses: u64,
+        /// Rating with Sudoku Explainer's technique weights, see
+        /// [`se_rating`](crate::logic::logic::se_rating), or `None` if the logical solver
+        /// can't finish the puzzle
+        pub se: Option<f64>,
+    }
+
+    /// Rates a puzzle by how much guessing the exact solver needs. Only uniquely
+    /// solvable puzzles can be rated.
+    pub fn rate(board: &Board) -> Result<Rating, Box<dyn std::error::Error>> {
+        let (_, stats) = solve_with_stats(board)?;
+        if count_solutions(board, 2) != 1 {
+            return Err(Box::from("Puzzle has more than one solution"));
+        }
+
+        let difficulty = match stats.guesses {
+            0 => Difficulty::Easy,
+            1..=5 => Difficulty::Medium,
+            6..=50 => Difficulty::Hard,
+            _ => Difficulty::Expert,
+        };
+
+        let se = se_rating(board)?;
+        return Ok(Rating {
+            difficulty,
+            clues: board.clue_count(),
+            guesses: stats.guesses,
+            se: se.complete.then_some(se.rating),
+        });
+    }
+}
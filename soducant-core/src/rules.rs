@@ -0,0 +1,139 @@
+pub mod rules {
+    use crate::constraint::constraint::parse_cell;
+    use crate::sudoku::sudoku::{BOARD_SIZE, Board, Cage, Regions, WINDOKU};
+    use crate::variants::variants::{
+        AntiKing, AntiKnight, Arrow, Dot, Inequality, Marker, Markers, Parity, Sandwich,
+        Thermometer,
+    };
+
+    /// Adds variant rules to a board, one per line: `cage <sum> r1c1 ...` for killer cages,
+    /// `diagonal` for Sudoku X, `regions <81 symbols>` for jigsaw regions, `windoku` for the
+    /// four hyper-sudoku windows, `extra r1c1 ...` for any other nine cells that hold every
+    /// digit, `anti-knight` or `anti-king` for the chess variants, `thermo r1c1 ...` from the
+    /// bulb, `arrow r1c1 ...` from the circle, `sandwich r3 15` or `sandwich c5 0`, `odd r1c1
+    /// ...` or `even r1c1 ...` for shaded cells, `greater r1c1 r1c2` when the first cell is the
+    /// larger, and markers between neighbours as `white`, `black`, `x` or `v` followed by the
+    /// two cells. `negative kropki` or `negative xv` says every such marker is shown.
+    pub fn apply_rules<'a>(
+        board: &mut Board,
+        lines: impl IntoIterator<Item = &'a str>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        // Markers are added together at the end, since the negative constraint depends on all
+        // of them
+        let mut dots = Vec::new();
+        let mut negative = Vec::new();
+        for line in lines {
+            let line = line.trim();
+            let (rule, rest) = line.split_once(' ').unwrap_or((line, ""));
+            match rule {
+                "cage" => board.add_cage(rest.parse::<Cage>()?)?,
+                "diagonal" => board.set_diagonal(true),
+                "regions" => board.set_regions(rest.parse::<Regions>()?),
+                "windoku" => board.add_windoku(),
+                "extra" => {
+                    let cells = rest
+                        .split_whitespace()
+                        .map(parse_cell)
+                        .collect::<Result<Vec<_>, String>>()?;
+                    let cells = cells.try_into().map_err(|cells: Vec<_>| {
+                        format!("Extra region has {} cells, needs 9", cells.len())
+                    })?;
+                    board.add_extra_region(cells)?;
+                }
+                "anti-knight" => board.add_constraint(AntiKnight),
+                "anti-king" => board.add_constraint(AntiKing),
+                "thermo" => board.add_constraint(rest.parse::<Thermometer>()?),
+                "arrow" => board.add_constraint(rest.parse::<Arrow>()?),
+                "sandwich" => board.add_constraint(rest.parse::<Sandwich>()?),
+                "odd" | "even" => board.add_constraint(line.parse::<Parity>()?),
+                "greater" => board.add_constraint(rest.parse::<Inequality>()?),
+                "white" | "black" | "x" | "v" => dots.push(line.parse::<Dot>()?),
+                "negative" => match rest.trim() {
+                    "kropki" => negative.extend([Marker::White, Marker::Black]),
+                    "xv" => negative.extend([Marker::X, Marker::V]),
+                    _ => return Err(Box::from(format!("Unknown negative rule: {}", line))),
+                },
+                _ => return Err(Box::from(format!("Unknown puzzle rule: {}", line))),
+            }
+        }
+        if !dots.is_empty() || !negative.is_empty() {
+            board.add_constraint(Markers::new(dots, negative)?);
+        }
+        return Ok(());
+    }
+
+    /// The rule lines [`apply_rules`] needs to give a plain board the same variants, empty for
+    /// a classic sudoku
+    pub fn rule_lines(board: &Board) -> Vec<String> {
+        let mut lines = Vec::new();
+        if board.is_diagonal() {
+            lines.push("diagonal".to_string());
+        }
+        if !board.regions().is_boxes() {
+            lines.push(format!("regions {}", board.regions()));
+        }
+
+        let windows: Vec<[(usize, usize); BOARD_SIZE]> = WINDOKU
+            .iter()
+            .map(|&(top, left)| std::array::from_fn(|k| (top + k / 3, left + k % 3)))
+            .collect();
+        let mut extra = board.extra_regions();
+        if extra.starts_with(&windows) {
+            lines.push("windoku".to_string());
+            extra = &extra[windows.len()..];
+        }
+        for cells in extra {
+            let cells: Vec<String> = cells
+                .iter()
+                .map(|(i, j)| format!("r{}c{}", i + 1, j + 1))
+                .collect();
+            lines.push(format!("extra {}", cells.join(" ")));
+        }
+
+        for constraint in board.variant_constraints() {
+            if let Some(rule) = constraint.rule() {
+                lines.extend(rule.lines().map(str::to_string));
+            }
+        }
+        return lines;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rules::rules::{apply_rules, rule_lines};
+    use crate::sudoku::sudoku::Board;
+
+    #[test]
+    fn rules_round_trip() {
+        let rules = [
+            "diagonal",
+            "windoku",
+            "extra r1c1 r1c2 r1c3 r1c4 r1c5 r1c6 r1c7 r1c8 r1c9",
+            "cage 10 r9c1 r9c2",
+            "anti-king",
+            "thermo r2c2 r2c3 r2c4",
+            "arrow r5c5 r6c5 r7c5",
+            "sandwich c5 0",
+            "odd r1c1 r3c3",
+            "even r9c9",
+            "greater r4c4 r4c5",
+            "white r8c8 r8c9",
+            "x r7c1 r7c2",
+            "negative kropki",
+        ];
+        let mut board = Board::default();
+        apply_rules(&mut board, rules).unwrap();
+        assert_eq!(rule_lines(&board), rules);
+
+        let mut copy = Board::default();
+        let lines = rule_lines(&board);
+        apply_rules(&mut copy, lines.iter().map(String::as_str)).unwrap();
+        assert_eq!(rule_lines(&copy), rules);
+
+        assert!(rule_lines(&Board::default()).is_empty());
+        assert!(apply_rules(&mut copy, ["greater r1c1 r3c3"]).is_err());
+        assert!(apply_rules(&mut copy, ["odd"]).is_err());
+        assert!(apply_rules(&mut copy, ["purple r1c1"]).is_err());
+    }
+}
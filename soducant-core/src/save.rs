@@ -0,0 +1,385 @@
+pub mod save {
+    use serde::{Deserialize, Serialize};
+    use std::time::Duration;
+
+    use crate::rules::rules::{apply_rules, rule_lines};
+    use crate::session::session::{CellState, Change, GameSession};
+    use crate::sudoku::sudoku::{BOARD_SIZE, Board};
+
+    /// Bumped whenever either save format changes
+    pub const SAVE_VERSION: u32 = 2;
+
+    const TEXT_PREFIX: &str = "soducant-save";
+    const CELLS: usize = BOARD_SIZE * BOARD_SIZE;
+
+    #[derive(Serialize, Deserialize)]
+    struct SaveJson {
+        version: u32,
+        givens: String,
+        entries: String,
+        notes: Vec<u16>,
+        elapsed_ms: u64,
+        mistakes: u32,
+        history: Vec<Change>,
+        redo: Vec<Change>,
+        /// Variant rules, as read by [`apply_rules`]
+        rules: Vec<String>,
+    }
+
+    fn entries_string(session: &GameSession) -> String {
+        let mut out = String::with_capacity(CELLS);
+        for row in &session.cells {
+            for cell in row {
+                out += &cell.value.unwrap_or(0).to_string();
+            }
+        }
+        return out;
+    }
+
+    fn encode_cell(cell: &CellState) -> String {
+        return format!("{}{:03x}", cell.value.unwrap_or(0), cell.notes);
+    }
+
+    fn encode_changes(changes: &[Change]) -> String {
+        let mut out = String::new();
+        for change in changes {
+            out += &format!(
+                "{}{}{}{}",
+                change.row,
+                change.col,
+                encode_cell(&change.before),
+                encode_cell(&change.after)
+            );
+        }
+        return out;
+    }
+
+    /// Compact single-line form: `soducant-save:<version>:<givens>:<entries>:<notes>:<elapsed
+    /// ms>:<mistakes>:<history>:<redo>:<rules>`. Boards are 81 digits, notes are three hex
+    /// digits per cell and each change is ten characters (row, col, then value and notes before
+    /// and after). Rules are the lines of [`rule_lines`] joined by `;`, empty for a classic
+    /// sudoku.
+    pub fn to_text(session: &GameSession) -> String {
+        let mut notes = String::with_capacity(CELLS * 3);
+        for row in &session.cells {
+            for cell in row {
+                notes += &format!("{:03x}", cell.notes);
+            }
+        }
+        return format!(
+            "{}:{}:{}:{}:{}:{}:{}:{}:{}:{}",
+            TEXT_PREFIX,
+            SAVE_VERSION,
+            session.givens.to_clean_string(),
+            entries_string(session),
+            notes,
+            session.elapsed.as_millis(),
+            session.mistakes,
+            encode_changes(&session.undo),
+            encode_changes(&session.redo),
+            rule_lines(&session.givens).join(";")
+        );
+    }
+
+    pub fn to_json(session: &GameSession) -> String {
+        let save = SaveJson {
+            version: SAVE_VERSION,
+            givens: session.givens.to_clean_string(),
+            entries: entries_string(session),
+            notes: session.cells.iter().flatten().map(|c| c.notes).collect(),
+            elapsed_ms: session.elapsed.as_millis() as u64,
+            mistakes: session.mistakes,
+            history: session.undo.clone(),
+            redo: session.redo.clone(),
+            rules: rule_lines(&session.givens),
+        };
+        return serde_json::to_string(&save).expect("save data always serializes");
+    }
+
+    fn parse_digit(c: char) -> Result<Option<i8>, Box<dyn std::error::Error>> {
+        let digit = c
+            .to_digit(10)
+            .ok_or_else(|| format!("Expected a digit in save, found {}", c))?;
+        return Ok(if digit == 0 { None } else { Some(digit as i8) });
+    }
+
+    fn parse_notes(hex: &str) -> Result<u16, Box<dyn std::error::Error>> {
+        return Ok(
+            u16::from_str_radix(hex, 16).map_err(|_| format!("Invalid notes in save: {}", hex))?
+        );
+    }
+
+    fn decode_cell(text: &str) -> Result<CellState, Box<dyn std::error::Error>> {
+        let value = parse_digit(text.chars().next().ok_or("Truncated save")?)?;
+        let notes = parse_notes(text.get(1..4).ok_or("Truncated save")?)?;
+        return Ok(CellState { value, notes });
+    }
+
+    fn decode_changes(text: &str) -> Result<Vec<Change>, Box<dyn std::error::Error>> {
+        if !text.is_ascii() || !text.len().is_multiple_of(10) {
+            return Err(Box::from("Move history in save is malformed"));
+        }
+        let mut out = Vec::new();
+        for i in (0..text.len()).step_by(10) {
+            let chunk = &text[i..i + 10];
+            let mut coords = chunk.chars().take(2).map(|c| c.to_digit(10));
+            let (Some(Some(row)), Some(Some(col))) = (coords.next(), coords.next()) else {
+                return Err(Box::from("Move history in save is malformed"));
+            };
+            out.push(Change {
+                row: row as usize,
+                col: col as usize,
+                before: decode_cell(&chunk[2..6])?,
+                after: decode_cell(&chunk[6..10])?,
+            });
+        }
+        return Ok(out);
+    }
+
+    fn check_version(version: u32) -> Result<(), Box<dyn std::error::Error>> {
+        if version != SAVE_VERSION {
+            return Err(Box::from(format!(
+                "Save version {} is not supported (expected {})",
+                version, SAVE_VERSION
+            )));
+        }
+        return Ok(());
+    }
+
+    fn check_cell(cell: &CellState) -> Result<(), Box<dyn std::error::Error>> {
+        if cell.notes >= 1 << BOARD_SIZE || cell.value.is_some_and(|v| !(1..=9).contains(&v)) {
+            return Err(Box::from("Save contains an invalid cell"));
+        }
+        return Ok(());
+    }
+
+    /// Rebuilds a session, checking that the saved state fits the givens
+    #[allow(clippy::too_many_arguments)]
+    fn restore(
+        givens: &str,
+        entries: &str,
+        notes: &[u16],
+        elapsed_ms: u64,
+        mistakes: u32,
+        history: Vec<Change>,
+        redo: Vec<Change>,
+        rules: &[&str],
+    ) -> Result<GameSession, Box<dyn std::error::Error>> {
+        let mut board = Board::from_string(givens)?;
+        apply_rules(&mut board, rules.iter().copied())?;
+        let mut session = GameSession::new(board)?;
+        let entries = Board::from_string(entries)?;
+        if notes.len() != CELLS {
+            return Err(Box::from("Save must have notes for every cell"));
+        }
+
+        for row in 0..BOARD_SIZE {
+            for col in 0..BOARD_SIZE {
+                let cell = CellState {
+                    value: entries.get(row, col),
+                    notes: notes[row * BOARD_SIZE + col],
+                };
+                check_cell(&cell)?;
+                if session.is_given(row, col)? && cell != CellState::default() {
+                    return Err(Box::from(format!(
+                        "Save has an entry on the given at ({},{})",
+                        row, col
+                    )));
+                }
+                session.cells[row][col] = cell;
+            }
+        }
+        for change in history.iter().chain(redo.iter()) {
+            check_cell(&change.before)?;
+            check_cell(&change.after)?;
+            if change.row >= BOARD_SIZE
+                || change.col >= BOARD_SIZE
+                || session.is_given(change.row, change.col)?
+            {
+                return Err(Box::from("Move history in save touches an invalid cell"));
+            }
+        }
+
+        session.undo = history;
+        session.redo = redo;
+        session.mistakes = mistakes;
+        session.elapsed = Duration::from_millis(elapsed_ms);
+        return Ok(session);
+    }
+
+    pub fn from_text(text: &str) -> Result<GameSession, Box<dyn std::error::Error>> {
+        let fields: Vec<&str> = text.trim().split(':').collect();
+        if fields.len() < 2 || fields[0] != TEXT_PREFIX {
+            return Err(Box::from("Not a soducant save"));
+        }
+        check_version(
+            fields[1]
+                .parse()
+                .map_err(|_| "Save version is not a number")?,
+        )?;
+        if fields.len() != 10 {
+            return Err(Box::from("Not a soducant save"));
+        }
+
+        let notes_text = fields[4];
+        if !notes_text.is_ascii() || notes_text.len() != CELLS * 3 {
+            return Err(Box::from("Save must have notes for every cell"));
+        }
+        let notes = (0..CELLS)
+            .map(|i| parse_notes(&notes_text[i * 3..i * 3 + 3]))
+            .collect::<Result<Vec<u16>, _>>()?;
+
+        return restore(
+            fields[2],
+            fields[3],
+            &notes,
+            fields[5].parse().map_err(|_| "Save time is not a number")?,
+            fields[6]
+                .parse()
+                .map_err(|_| "Save mistakes is not a number")?,
+            decode_changes(fields[7])?,
+            decode_changes(fields[8])?,
+            &fields[9]
+                .split(';')
+                .filter(|line| !line.is_empty())
+                .collect::<Vec<_>>(),
+        );
+    }
+
+    pub fn from_json(text: &str) -> Result<GameSession, Box<dyn std::error::Error>> {
+        let value: serde_json::Value = serde_json::from_str(text)?;
+        check_version(
+            value["version"]
+                .as_u64()
+                .ok_or("Save version is not a number")? as u32,
+        )?;
+        let save: SaveJson = serde_json::from_value(value)?;
+        return restore(
+            &save.givens,
+            &save.entries,
+            &save.notes,
+            save.elapsed_ms,
+            save.mistakes,
+            save.history,
+            save.redo,
+            &save.rules.iter().map(String::as_str).collect::<Vec<_>>(),
+        );
+    }
+
+    /// Loads either format
+    pub fn load(text: &str) -> Result<GameSession, Box<dyn std::error::Error>> {
+        if text.trim_start().starts_with('{') {
+            return from_json(text);
+        }
+        return from_text(text);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::rules::rules::{apply_rules, rule_lines};
+    use crate::save::save::{from_json, from_text, load, to_json, to_text};
+    use crate::session::session::GameSession;
+    use crate::sudoku::sudoku::Board;
+
+    fn played() -> GameSession {
+        let board = Board::from_string(
+            "278000401609100050005006900430809000706003000091000800000020173860001004107934685",
+        )
+        .unwrap();
+        let mut game = GameSession::new(board).unwrap();
+        game.set_value(0, 3, Some(2)).unwrap();
+        game.toggle_note(0, 4, 9).unwrap();
+        game.toggle_note(0, 4, 8).unwrap();
+        game.set_value(1, 1, Some(4)).unwrap();
+        game.undo();
+        game.tick(Duration::from_millis(61500));
+        return game;
+    }
+
+    fn assert_same(a: &GameSession, b: &GameSession) {
+        assert_eq!(a.board().to_clean_string(), b.board().to_clean_string());
+        assert_eq!(a.givens().to_clean_string(), b.givens().to_clean_string());
+        assert_eq!(a.cell(0, 4).unwrap(), b.cell(0, 4).unwrap());
+        assert_eq!(a.history(), b.history());
+        assert_eq!(a.mistakes(), b.mistakes());
+        assert_eq!(a.elapsed(), b.elapsed());
+    }
+
+    #[test]
+    fn round_trips_both_formats() {
+        let game = played();
+
+        let mut from_text = from_text(&to_text(&game)).unwrap();
+        assert_same(&game, &from_text);
+        let mut from_json = from_json(&to_json(&game)).unwrap();
+        assert_same(&game, &from_json);
+
+        // The undone move survives too
+        assert_eq!(from_text.redo(), Some((1, 1)));
+        assert_eq!(from_json.redo(), Some((1, 1)));
+        assert_eq!(from_json.value(1, 1).unwrap(), Some(4));
+    }
+
+    #[test]
+    fn rejects_bad_saves() {
+        let text = to_text(&played());
+        assert!(load(&text.replace("soducant-save:2:", "soducant-save:3:")).is_err());
+        // Version 1 had no rules, so a variant game would come back as a classic one
+        let old = text.replace("soducant-save:2:", "soducant-save:1:");
+        assert!(load(old.strip_suffix(':').unwrap()).is_err());
+        assert!(load(&text.replace(":278000401", ":778000401")).is_err());
+        assert!(load("not a save").is_err());
+
+        let json = to_json(&played());
+        assert!(load(&json.replace("\"version\":2", "\"version\":9")).is_err());
+        assert!(load(&json.replace("\"version\":2", "\"version\":1")).is_err());
+        // An entry on top of a given
+        assert!(load(&json.replace("\"entries\":\"0", "\"entries\":\"5")).is_err());
+    }
+
+    #[test]
+    fn keeps_variant_rules() {
+        let mut board = Board::from_string(
+            "020450709078061004001000402700092000000100000306245100050024800000039000007008020",
+        )
+        .unwrap();
+        let rules = [
+            "regions 412223333411122233411122663441526633441555666445585996777785996777785999788888899",
+            "cage 3 r1c1 r1c2",
+        ];
+        apply_rules(&mut board, rules).unwrap();
+        let mut game = GameSession::new(board).unwrap();
+        game.set_value(0, 0, Some(2)).unwrap();
+
+        for loaded in [
+            from_text(&to_text(&game)).unwrap(),
+            from_json(&to_json(&game)).unwrap(),
+        ] {
+            assert_same(&game, &loaded);
+            assert_eq!(rule_lines(loaded.givens()), rule_lines(game.givens()));
+            assert_eq!(
+                loaded.solution().to_clean_string(),
+                game.solution().to_clean_string()
+            );
+            assert!(loaded.is_mistake(0, 0).unwrap());
+        }
+    }
+
+    #[test]
+    fn keeps_parity_and_inequalities() {
+        let mut board = played().givens().clone();
+        apply_rules(&mut board, ["even r1c1", "odd r1c2", "greater r1c3 r1c4"]).unwrap();
+        let game = GameSession::new(board).unwrap();
+
+        for loaded in [
+            load(&to_text(&game)).unwrap(),
+            load(&to_json(&game)).unwrap(),
+        ] {
+            assert_eq!(rule_lines(loaded.givens()), rule_lines(game.givens()));
+            assert_eq!(rule_lines(loaded.givens()).len(), 3);
+        }
+    }
+}
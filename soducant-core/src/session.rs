@@ -0,0 +1,370 @@
+pub mod session {
+    use serde::{Deserialize, Serialize};
+    use std::time::Duration;
+
+    use crate::exact::exact;
+    use crate::hint::hint::{Hint, hint};
+    use crate::sudoku::sudoku::{BOARD_SIZE, Board};
+
+    /// What the player has put in a non-given cell
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+    pub struct CellState {
+        pub value: Option<i8>,
+        /// Bit `d - 1` is set when `d` is pencilled in
+        pub notes: u16,
+    }
+
+    impl CellState {
+        pub fn has_note(&self, digit: i8) -> bool {
+            return self.notes & (1 << (digit - 1)) != 0;
+        }
+    }
+
+    /// One step of the move history
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct Change {
+        pub row: usize,
+        pub col: usize,
+        pub before: CellState,
+        pub after: CellState,
+    }
+
+    /// State of a game in progress, shared by every frontend. Time is advanced by the
+    /// frontend through [`GameSession::tick`], so no clock is needed here.
+    #[derive(Debug, Clone)]
+    pub struct GameSession {
+        pub(crate) givens: Board,
+        pub(crate) solution: Board,
+        pub(crate) cells: [[CellState; BOARD_SIZE]; BOARD_SIZE],
+        pub(crate) undo: Vec<Change>,
+        pub(crate) redo: Vec<Change>,
+        pub(crate) mistakes: u32,
+        pub(crate) elapsed: Duration,
+    }
+
+    impl GameSession {
+        /// Starts a game on `givens`. Mistakes are counted against the first solution the exact
+        /// solver finds, so puzzles should have a unique solution.
+        pub fn new(givens: Board) -> Result<Self, Box<dyn std::error::Error>> {
+            let solution = exact::solve(&givens)?;
+            return Ok(GameSession {
+                givens,
+                solution,
+                cells: [[CellState::default(); BOARD_SIZE]; BOARD_SIZE],
+                undo: Vec::new(),
+                redo: Vec::new(),
+                mistakes: 0,
+                elapsed: Duration::ZERO,
+            });
+        }
+
+        pub fn givens(&self) -> &Board {
+            return &self.givens;
+        }
+
+        pub fn solution(&self) -> &Board {
+            return &self.solution;
+        }
+
+        pub fn is_given(&self, row: usize, col: usize) -> Result<bool, Box<dyn std::error::Error>> {
+            check_position(row, col)?;
+            return Ok(self.given(row, col));
+        }
+
+        /// The given or entered digit in a cell
+        pub fn value(
+            &self,
+            row: usize,
+            col: usize,
+        ) -> Result<Option<i8>, Box<dyn std::error::Error>> {
+            check_position(row, col)?;
+            return Ok(self.current(row, col));
+        }
+
+        pub fn cell(
+            &self,
+            row: usize,
+            col: usize,
+        ) -> Result<CellState, Box<dyn std::error::Error>> {
+            check_position(row, col)?;
+            return Ok(self.cells[row][col]);
+        }
+
+        fn given(&self, row: usize, col: usize) -> bool {
+            return self.givens.get(row, col).is_some();
+        }
+
+        fn current(&self, row: usize, col: usize) -> Option<i8> {
+            return self.givens.get(row, col).or(self.cells[row][col].value);
+        }
+
+        /// Givens and entries combined
+        pub fn board(&self) -> Board {
+            let mut board = self.givens.clone();
+            for row in 0..BOARD_SIZE {
+                for col in 0..BOARD_SIZE {
+                    board.set(row, col, self.current(row, col));
+                }
+            }
+            return board;
+        }
+
+        fn apply(
+            &mut self,
+            row: usize,
+            col: usize,
+            after: CellState,
+        ) -> Result<(), Box<dyn std::error::Error>> {
+            if self.given(row, col) {
+                return Err(Box::from(format!("({},{}) is a given", row, col)));
+            }
+            let before = self.cells[row][col];
+            if before == after {
+                return Ok(());
+            }
+            if after.value.is_some()
+                && after.value != before.value
+                && !self.is_correct(row, col, after.value)
+            {
+                self.mistakes += 1;
+            }
+            self.cells[row][col] = after;
+            self.undo.push(Change {
+                row,
+                col,
+                before,
+                after,
+            });
+            self.redo.clear();
+            return Ok(());
+        }
+
+        fn is_correct(&self, row: usize, col: usize, value: Option<i8>) -> bool {
+            return value == self.solution.get(row, col);
+        }
+
+        /// Enters a digit, or clears the entry with `None`. Notes are kept.
+        pub fn set_value(
+            &mut self,
+            row: usize,
+            col: usize,
+            value: Option<i8>,
+        ) -> Result<(), Box<dyn std::error::Error>> {
+            if let Some(digit) = value
+                && !(1..=9).contains(&digit)
+            {
+                return Err(Box::from(format!("{} is not a digit from 1 to 9", digit)));
+            }
+            check_position(row, col)?;
+            let mut cell = self.cells[row][col];
+            cell.value = value;
+            return self.apply(row, col, cell);
+        }
+
+        pub fn toggle_note(
+            &mut self,
+            row: usize,
+            col: usize,
+            digit: i8,
+        ) -> Result<(), Box<dyn std::error::Error>> {
+            if !(1..=9).contains(&digit) {
+                return Err(Box::from(format!("{} is not a digit from 1 to 9", digit)));
+            }
+            check_position(row, col)?;
+            let mut cell = self.cells[row][col];
+            cell.notes ^= 1 << (digit - 1);
+            return self.apply(row, col, cell);
+        }
+
+        /// Removes both the entry and the notes of a cell
+        pub fn clear(&mut self, row: usize, col: usize) -> Result<(), Box<dyn std::error::Error>> {
+            check_position(row, col)?;
+            return self.apply(row, col, CellState::default());
+        }
+
+        /// Reverts the last change, returning the cell it touched
+        pub fn undo(&mut self) -> Option<(usize, usize)> {
+            let change = self.undo.pop()?;
+            self.cells[change.row][change.col] = change.before;
+            self.redo.push(change);
+            return Some((change.row, change.col));
+        }
+
+        /// Reapplies the last undone change, returning the cell it touched
+        pub fn redo(&mut self) -> Option<(usize, usize)> {
+            let change = self.redo.pop()?;
+            self.cells[change.row][change.col] = change.after;
+            self.undo.push(change);
+            return Some((change.row, change.col));
+        }
+
+        pub fn can_undo(&self) -> bool {
+            return !self.undo.is_empty();
+        }
+
+        pub fn can_redo(&self) -> bool {
+            return !self.redo.is_empty();
+        }
+
+        /// Changes made so far, oldest first, not counting undone ones
+        pub fn history(&self) -> &[Change] {
+            return &self.undo;
+        }
+
+        /// Number of times a wrong digit was entered. Undoing doesn't take mistakes back.
+        pub fn mistakes(&self) -> u32 {
+            return self.mistakes;
+        }
+
+        /// Whether the entry in a cell differs from the solution
+        pub fn is_mistake(
+            &self,
+            row: usize,
+            col: usize,
+        ) -> Result<bool, Box<dyn std::error::Error>> {
+            check_position(row, col)?;
+            let entry = self.cells[row][col].value;
+            return Ok(!self.given(row, col)
+                && entry.is_some()
+                && !self.is_correct(row, col, entry));
+        }
+
+        /// Cells that share a row, column, region or other house with an equal digit
+        pub fn conflicts(&self) -> [[bool; BOARD_SIZE]; BOARD_SIZE] {
+            let mut out = [[false; BOARD_SIZE]; BOARD_SIZE];
+            for (row, col) in self.board().conflicts().into_iter().flatten() {
+                out[row][col] = true;
+            }
+            return out;
+        }
+
+        /// Whether a cell shares a house with an equal digit, like [`GameSession::conflicts`]
+        pub fn is_conflict(
+            &self,
+            row: usize,
+            col: usize,
+        ) -> Result<bool, Box<dyn std::error::Error>> {
+            check_position(row, col)?;
+            return Ok(self.conflicts()[row][col]);
+        }
+
+        /// Suggests the next digit without placing it. Fails if an entry is wrong.
+        pub fn hint(&self) -> Result<Option<Hint>, Box<dyn std::error::Error>> {
+            return hint(&self.board());
+        }
+
+        pub fn is_solved(&self) -> bool {
+            return (0..BOARD_SIZE).all(|row| {
+                (0..BOARD_SIZE).all(|col| self.current(row, col) == self.solution.get(row, col))
+            });
+        }
+
+        /// Adds play time, unless the puzzle is already solved
+        pub fn tick(&mut self, delta: Duration) {
+            if !self.is_solved() {
+                self.elapsed += delta;
+            }
+        }
+
+        pub fn elapsed(&self) -> Duration {
+            return self.elapsed;
+        }
+    }
+
+    fn check_position(row: usize, col: usize) -> Result<(), Box<dyn std::error::Error>> {
+        if row >= BOARD_SIZE || col >= BOARD_SIZE {
+            return Err(Box::from(format!("({},{}) is off the board", row, col)));
+        }
+        return Ok(());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::session::session::GameSession;
+    use crate::sudoku::sudoku::Board;
+
+    fn session() -> GameSession {
+        let board = Board::from_string(
+            "278000401609100050005006900430809000706003000091000800000020173860001004107934685",
+        )
+        .unwrap();
+        return GameSession::new(board).unwrap();
+    }
+
+    #[test]
+    fn undo_and_redo_entries() {
+        let mut game = session();
+        game.set_value(0, 3, Some(3)).unwrap();
+        game.toggle_note(0, 4, 9).unwrap();
+
+        assert_eq!(game.undo(), Some((0, 4)));
+        assert!(!game.cell(0, 4).unwrap().has_note(9));
+        assert_eq!(game.redo(), Some((0, 4)));
+        assert!(game.cell(0, 4).unwrap().has_note(9));
+
+        assert_eq!(game.undo(), Some((0, 4)));
+        assert_eq!(game.undo(), Some((0, 3)));
+        assert_eq!(game.value(0, 3).unwrap(), None);
+        assert_eq!(game.undo(), None);
+
+        game.set_value(1, 1, Some(4)).unwrap();
+        assert!(!game.can_redo());
+        assert!(game.set_value(0, 0, Some(1)).is_err());
+    }
+
+    #[test]
+    fn counts_mistakes_and_time() {
+        let mut game = session();
+        game.set_value(0, 3, Some(2)).unwrap();
+        assert_eq!(game.mistakes(), 1);
+        assert!(game.is_mistake(0, 3).unwrap());
+        assert!(game.conflicts()[0][3]);
+        assert!(game.is_conflict(0, 3).unwrap());
+
+        game.undo();
+        game.redo();
+        assert_eq!(game.mistakes(), 1);
+
+        game.set_value(0, 3, Some(3)).unwrap();
+        assert_eq!(game.mistakes(), 1);
+        assert!(!game.is_mistake(0, 3).unwrap());
+
+        game.tick(Duration::from_secs(3));
+        assert_eq!(game.elapsed(), Duration::from_secs(3));
+    }
+
+    #[test]
+    fn rejects_cells_off_the_board() {
+        let mut game = session();
+        game.cell(9, 0).unwrap_err();
+        game.value(0, 9).unwrap_err();
+        game.is_given(9, 9).unwrap_err();
+        game.is_mistake(10, 0).unwrap_err();
+        game.is_conflict(0, 10).unwrap_err();
+        game.set_value(9, 0, Some(1)).unwrap_err();
+        game.toggle_note(0, 9, 1).unwrap_err();
+        game.clear(9, 9).unwrap_err();
+        assert!(!game.can_undo());
+    }
+
+    #[test]
+    fn stops_the_clock_when_solved() {
+        let mut game = session();
+        let solution = game.solution().clone();
+        for row in 0..9 {
+            for col in 0..9 {
+                if !game.is_given(row, col).unwrap() {
+                    game.set_value(row, col, solution.get(row, col)).unwrap();
+                }
+            }
+        }
+
+        assert!(game.is_solved());
+        game.tick(Duration::from_secs(3));
+        assert_eq!(game.elapsed(), Duration::ZERO);
+        assert_eq!(game.mistakes(), 0);
+    }
+}
@@ -1,19 +1,72 @@
 pub mod solver {
-    use rand::Rng;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
 
-    use crate::sudoku::sudoku::{Board, Solution};
+    use crate::sudoku::sudoku::{BOARD_SIZE, Board, Solution};
 
-    use std::time::Instant;
+    use std::time::{Duration, Instant};
+
+    /// Annealing schedule used by a [`Solver`].
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct SolverParams {
+        pub temperature: f64,
+        pub decay: f64,
+        /// Steps without improvement before the solution gets shaken up
+        pub reheat_threshold: u32,
+        /// Number of random swaps applied when reheating
+        pub reheat_strength: u32,
+    }
+
+    impl Default for SolverParams {
+        fn default() -> Self {
+            SolverParams {
+                temperature: 9.0,
+                decay: 0.980,
+                reheat_threshold: 750,
+                reheat_strength: 100,
+            }
+        }
+    }
+
+    impl SolverParams {
+        /// Parameters used by [`solve`] when the default schedule fails
+        pub fn fallback() -> Self {
+            SolverParams {
+                temperature: 10.0,
+                decay: 0.99,
+                ..Self::default()
+            }
+        }
+    }
 
     pub struct Solver {
         pub temperature: f64,
         pub decay: f64,
+        pub reheat_threshold: u32,
+        pub reheat_strength: u32,
         pub board: Board,
         pub current_score: u32,
         pub solution: Solution,
         time_since_last_improvement: u32,
+        rng: StdRng,
+    }
+
+    /// Options for [`solve_with_options`]
+    #[derive(Debug, Clone, Default)]
+    pub struct SolveOptions {
+        /// Seed for the annealer, for reproducible runs
+        pub seed: Option<u64>,
+        /// Keep restarting the annealer until this much time has passed. Boards with houses
+        /// that overlap the regions get [`OVERLAP_TIME_LIMIT`] when this is `None`.
+        pub time_limit: Option<Duration>,
     }
 
+    /// Time limit for boards with houses that overlap the regions, such as Sudoku X and
+    /// windoku, when none is given. Their runs are 30 times longer, up to 4.5 million steps,
+    /// which an unsolvable board would otherwise spend in full. Windoku took up to 18 seconds
+    /// over 12 seeds on one core.
+    pub const OVERLAP_TIME_LIMIT: Duration = Duration::from_secs(30);
+
     static PUZZLE_CACHE: &str = include_str!("test_sudokus.txt");
 
     impl Default for Solver {
@@ -23,13 +76,17 @@ pub mod solver {
             )
             .unwrap();
             let s = Solution::new_from_board(&b).unwrap();
+            let params = SolverParams::default();
             Solver {
-                temperature: 9.0,
-                decay: 0.980,
+                temperature: params.temperature,
+                decay: params.decay,
+                reheat_threshold: params.reheat_threshold,
+                reheat_strength: params.reheat_strength,
                 current_score: b.score_solution(&s).unwrap(),
                 solution: s,
                 board: b,
                 time_since_last_improvement: 0,
+                rng: StdRng::from_os_rng(),
             }
         }
     }
@@ -45,6 +102,29 @@ pub mod solver {
             return solver;
         }
 
+        pub fn new_with_solver_params(board: &str, params: &SolverParams) -> Self {
+            return Self::from_board(Board::from_string(board).unwrap(), params).unwrap();
+        }
+
+        /// Starts from a parsed board, keeping anything a string can't hold, such as cages
+        pub fn from_board(
+            board: Board,
+            params: &SolverParams,
+        ) -> Result<Self, Box<dyn std::error::Error>> {
+            let solution = Solution::new_from_board(&board)?;
+            return Ok(Solver {
+                temperature: params.temperature,
+                decay: params.decay,
+                reheat_threshold: params.reheat_threshold,
+                reheat_strength: params.reheat_strength,
+                current_score: board.score_solution(&solution)?,
+                solution,
+                board,
+                time_since_last_improvement: 0,
+                rng: StdRng::from_os_rng(),
+            });
+        }
+
         pub fn new_with_board(board: &str) -> Self {
             let mut solver = Self::default();
             solver.board = Board::from_string(board).unwrap();
@@ -53,13 +133,26 @@ pub mod solver {
             return solver;
         }
 
+        /// Makes the remaining run deterministic
+        pub fn seed(&mut self, seed: u64) {
+            self.rng = StdRng::seed_from_u64(seed);
+        }
+
         fn cool(&mut self) {
-            self.temperature = self.temperature * self.decay;
+            self.temperature *= self.decay;
         }
 
         fn solve_one_step(&mut self) -> Option<Solution> {
             let mut new_solution = self.solution.clone();
-            new_solution.flip_random_spaces();
+            // Houses beyond rows, columns and regions, such as windoku windows, cut across the
+            // regions that swaps keep whole, so some moves go after their repeated digits
+            let overlapping = &self.board.houses()[3 * BOARD_SIZE..];
+            if overlapping.is_empty()
+                || self.rng.random_bool(0.5)
+                || !new_solution.flip_repeated_space_with(overlapping, &mut self.rng)
+            {
+                new_solution.flip_random_spaces_with(&mut self.rng);
+            }
 
             let new_score = self.board.score_solution(&new_solution).unwrap();
 
@@ -81,8 +174,7 @@ pub mod solver {
                 .exp();
             self.cool();
 
-            let mut rng = rand::rng();
-            if rng.random_bool(p_accept) {
+            if self.rng.random_bool(p_accept) {
                 self.solution = new_solution;
                 self.current_score = new_score;
             }
@@ -90,14 +182,31 @@ pub mod solver {
         }
 
         pub fn solve(&mut self, max_iter: i32) -> Option<Solution> {
-            for _ in 0..max_iter {
+            return self.solve_until(max_iter, None);
+        }
+
+        /// Like [`Solver::solve`], but also gives up once `deadline` has passed
+        pub fn solve_until(
+            &mut self,
+            max_iter: i32,
+            deadline: Option<Instant>,
+        ) -> Option<Solution> {
+            // The starting fill already follows every rule when the board's rules leave each
+            // free cell a single digit
+            if self.current_score == 0 {
+                return Some(self.solution.clone());
+            }
+            for iter in 0..max_iter {
+                if iter % 1000 == 0 && deadline.is_some_and(|d| Instant::now() >= d) {
+                    return None;
+                }
                 let step = self.solve_one_step();
                 if let Some(solution) = step {
                     return Some(solution);
                 }
-                if self.time_since_last_improvement >= 750 {
-                    for _ in 0..100 {
-                        self.solution.flip_random_spaces();
+                if self.time_since_last_improvement >= self.reheat_threshold {
+                    for _ in 0..self.reheat_strength {
+                        self.solution.flip_random_spaces_with(&mut self.rng);
                     }
                     self.current_score = self.board.score_solution(&self.solution).unwrap();
                 }
@@ -106,61 +215,110 @@ pub mod solver {
         }
     }
 
-    pub fn benchmark(print_solutions: bool) {
+    #[derive(Debug, Clone, Copy)]
+    pub struct BenchmarkResult {
+        pub passes: usize,
+        pub attempts: usize,
+        pub duration: Duration,
+    }
+
+    impl BenchmarkResult {
+        pub fn pass_rate(&self) -> f32 {
+            return 100.0 * self.passes as f32 / self.attempts as f32;
+        }
+    }
+
+    /// Solves every bundled puzzle once. `on_puzzle` gets each puzzle and the solution found,
+    /// if any, so that callers can show progress.
+    pub fn run_benchmark(mut on_puzzle: impl FnMut(&Board, Option<&Solution>)) -> BenchmarkResult {
         let mut passes = 0;
         let mut attempts = 0;
 
         let start = Instant::now();
 
         for line in PUZZLE_CACHE.lines() {
-            if print_solutions {
-                let board = Board::from_string(line).unwrap();
-                println!("Now solving:\n{}", board);
-            }
             let mut solver = Solver::new_with_board(line);
             let solution = solver.solve(20000);
             if solution.is_some() {
                 passes += 1;
-                if print_solutions {
-                    println!("Solution found: \n{}", solution.unwrap());
-                }
-            } else if print_solutions {
-                println!("Couldn't find solution - miss");
             }
+            on_puzzle(&solver.board, solution.as_ref());
             attempts += 1;
         }
 
-        let duration = start.elapsed();
-
-        println!(
-            "Benchmarking results: {}/{} ({}%) passes",
+        return BenchmarkResult {
             passes,
             attempts,
-            100.0 * passes as f32 / attempts as f32
-        );
-        println!("Time taken: {:?}", duration); // Prints in a readable format
+            duration: start.elapsed(),
+        };
     }
 
     pub fn solve(board: &str) -> Result<String, Box<dyn std::error::Error>> {
-        let mut solver = Solver::new_with_board(board);
-        let solution = solver.solve(100000);
-        if let Some(answer) = solution {
-            return Ok(answer.to_clean_string());
-        }
+        return solve_with_options(board, &SolveOptions::default());
+    }
 
-        let mut solver = Solver::new_with_params(board, 10.0, 0.99);
-        let solution = solver.solve(50000);
-        if let Some(answer) = solution {
-            return Ok(answer.to_clean_string());
-        }
+    /// Solves with the default schedule, then the fallback one. With a time limit the two are
+    /// alternated until the time runs out.
+    pub fn solve_with_options(
+        board: &str,
+        options: &SolveOptions,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        let solution = solve_board_with_options(&Board::from_string(board)?, options)?;
+        return Ok(solution.to_clean_string());
+    }
+
+    /// Like [`solve_with_options`], for boards with cages or other rules beyond the digits
+    pub fn solve_board_with_options(
+        board: &Board,
+        options: &SolveOptions,
+    ) -> Result<Solution, Box<dyn std::error::Error>> {
+        // Overlapping houses make for a rougher landscape that needs far longer runs
+        let overlapping = board.houses().len() > 3 * BOARD_SIZE;
+        let length = if overlapping { 30 } else { 1 };
+        let attempts = [
+            (SolverParams::default(), 100000 * length),
+            (SolverParams::fallback(), 50000 * length),
+        ];
+        let time_limit = match options.time_limit {
+            None if overlapping => Some(OVERLAP_TIME_LIMIT),
+            time_limit => time_limit,
+        };
+        let deadline = time_limit.map(|limit| Instant::now() + limit);
+
+        let mut run = 0;
+        loop {
+            let (params, max_iter) = &attempts[run % attempts.len()];
+            let mut solver = Solver::from_board(board.clone(), params)?;
+            if let Some(seed) = options.seed {
+                solver.seed(seed.wrapping_add(run as u64));
+            }
+            if let Some(answer) = solver.solve_until(*max_iter, deadline) {
+                return Ok(answer);
+            }
+            run += 1;
 
-        return Err(Box::from("Couldn't find a solution"));
+            match deadline {
+                Some(deadline) if Instant::now() >= deadline => {
+                    return Err(Box::from("Couldn't find a solution within the time limit"));
+                }
+                Some(_) => {}
+                None if run >= attempts.len() => {
+                    return Err(Box::from("Couldn't find a solution"));
+                }
+                None => {}
+            }
+        }
     }
 
     pub fn solve_or_unwrap(board: &str) -> String {
         return solve(board).unwrap();
     }
 
+    /// Puzzles bundled with the crate, one 81-character string per line
+    pub fn test_puzzles() -> impl Iterator<Item = &'static str> {
+        return PUZZLE_CACHE.lines();
+    }
+
     pub fn get_random_sudoku() -> String {
         let mut rng = rand::rng();
         let choice = rng.random_range(0..500);
@@ -1,16 +1,294 @@
 pub mod sudoku {
-    use bit_vec::BitVec;
     use rand::prelude::*;
     use std::{
         fmt::{Display, Formatter},
-        ptr,
+        str::FromStr,
+        sync::Arc,
     };
 
-    const BOARD_SIZE: usize = 9;
+    use crate::constraint::constraint::{
+        ALL_DIGITS, Candidates, Constraint, Grid, House, Violation, digit_bit, parse_cell,
+    };
+
+    pub const BOARD_SIZE: usize = 9;
+
+    /// Random picks a swap gets to find one that keeps both digits allowed
+    const SWAP_DRAWS: usize = 10;
+
+    /// Top-left cells of the four windoku windows
+    pub const WINDOKU: [(usize, usize); 4] = [(1, 1), (1, 5), (5, 1), (5, 5)];
+
+    /// Killer sudoku cage: its cells must add up to `sum` without repeating a digit
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Cage {
+        pub sum: u32,
+        /// (row, col) pairs, 0-based
+        pub cells: Vec<(usize, usize)>,
+    }
+
+    impl Cage {
+        /// Parses one cage per line, skipping blank lines and lines starting with `#`
+        pub fn parse_list(text: &str) -> Result<Vec<Cage>, Box<dyn std::error::Error>> {
+            return text
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .map(str::parse)
+                .collect();
+        }
+
+        /// Digits used so far, how many times one repeated, their total and how many cells
+        /// are filled
+        fn tally(&self, grid: &Grid) -> (u16, u32, u32, u32) {
+            let mut seen = 0u16;
+            let mut repeats = 0;
+            let mut total = 0;
+            let mut filled = 0;
+            for &(row, col) in &self.cells {
+                let Some(num) = grid[row][col] else {
+                    continue;
+                };
+                if seen & digit_bit(num) != 0 {
+                    repeats += 1;
+                }
+                seen |= digit_bit(num);
+                total += num as u32;
+                filled += 1;
+            }
+            return (seen, repeats, total, filled);
+        }
+    }
+
+    impl Constraint for Cage {
+        fn violations(&self, grid: &Grid) -> Vec<Violation> {
+            let (_, repeats, total, filled) = self.tally(grid);
+            let complete = filled == self.cells.len() as u32;
+            if repeats > 0 || total > self.sum || (complete && total != self.sum) {
+                return vec![Violation {
+                    rule: format!("cage {}", self.sum),
+                    cells: self.cells.clone(),
+                }];
+            }
+            return Vec::new();
+        }
+
+        /// Repeated digits plus how far the cage is off its sum
+        fn penalty(&self, grid: &Grid) -> u32 {
+            let (_, repeats, total, _) = self.tally(grid);
+            return repeats + total.abs_diff(self.sum);
+        }
+
+        /// Keeps digits that leave a sum the remaining cells can still make
+        fn propagate(&self, grid: &Grid, candidates: &mut Candidates) {
+            let (used, repeats, total, filled) = self.tally(grid);
+            if repeats > 0 || total > self.sum {
+                return;
+            }
+            let left = self.cells.len() as u32 - filled;
+            if left == 0 {
+                return;
+            }
+            let mut allowed = 0;
+            for digit in 1..=9i8 {
+                let bit = digit_bit(digit);
+                let after = total + digit as u32;
+                if used & bit != 0 || after > self.sum {
+                    continue;
+                }
+                // Smallest and largest totals of the other empty cells
+                let free = !(used | bit) & ALL_DIGITS;
+                let digits = (1..=9u32).filter(|d| free & (1 << (d - 1)) != 0);
+                let min: u32 = digits.clone().take(left as usize - 1).sum();
+                let max: u32 = digits.rev().take(left as usize - 1).sum();
+                if (min..=max).contains(&(self.sum - after)) {
+                    allowed |= bit;
+                }
+            }
+            for &(row, col) in &self.cells {
+                if grid[row][col].is_none() {
+                    candidates.retain(row, col, allowed);
+                }
+            }
+        }
+
+        fn rule(&self) -> Option<String> {
+            return Some(format!("cage {}", self));
+        }
+    }
+
+    /// Reads `<sum> r<row>c<col> ...`, with 1-based rows and columns, e.g. `15 r1c1 r1c2 r2c1`
+    impl FromStr for Cage {
+        type Err = Box<dyn std::error::Error>;
+
+        fn from_str(line: &str) -> Result<Self, Self::Err> {
+            let mut parts = line.split_whitespace();
+            let sum = parts
+                .next()
+                .ok_or("Cage needs a sum")?
+                .parse()
+                .map_err(|_| format!("Cage sum is not a number: {}", line))?;
+            let cells = parts.map(parse_cell).collect::<Result<Vec<_>, String>>()?;
+            return Ok(Cage { sum, cells });
+        }
+    }
+
+    impl Display for Cage {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{}", self.sum)?;
+            for (row, col) in &self.cells {
+                write!(f, " r{}c{}", row + 1, col + 1)?;
+            }
+            Ok(())
+        }
+    }
+
+    /// Splits the board into nine regions of nine cells that can't repeat a digit. Standard
+    /// boards use the 3x3 boxes; jigsaw boards use irregular shapes.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Regions {
+        map: [[u8; BOARD_SIZE]; BOARD_SIZE],
+        /// Cells of each region in reading order, kept alongside the map for fast lookup
+        cells: [[(u8, u8); BOARD_SIZE]; BOARD_SIZE],
+    }
+
+    impl Regions {
+        /// The usual 3x3 boxes, numbered left to right, top to bottom
+        pub fn boxes() -> Self {
+            let mut map = [[0; BOARD_SIZE]; BOARD_SIZE];
+            for (i, row) in map.iter_mut().enumerate() {
+                for (j, region) in row.iter_mut().enumerate() {
+                    *region = (i / 3 * 3 + j / 3) as u8;
+                }
+            }
+            return Self::from_map(map).unwrap();
+        }
+
+        /// Checks that there are nine connected regions of nine cells each, numbered 0 to 8
+        pub fn from_map(
+            map: [[u8; BOARD_SIZE]; BOARD_SIZE],
+        ) -> Result<Self, Box<dyn std::error::Error>> {
+            let mut cells = [[(0, 0); BOARD_SIZE]; BOARD_SIZE];
+            let mut sizes = [0; BOARD_SIZE];
+            for (i, row) in map.iter().enumerate() {
+                for (j, &region) in row.iter().enumerate() {
+                    let region = region as usize;
+                    if region >= BOARD_SIZE {
+                        return Err(Box::from(format!("Region {} doesn't exist", region + 1)));
+                    }
+                    if sizes[region] == BOARD_SIZE {
+                        return Err(Box::from(format!(
+                            "Region {} has more than {} cells",
+                            region + 1,
+                            BOARD_SIZE
+                        )));
+                    }
+                    cells[region][sizes[region]] = (i as u8, j as u8);
+                    sizes[region] += 1;
+                }
+            }
+            // Every region has exactly nine cells now, since none has more
+
+            for (region, members) in cells.iter().enumerate() {
+                let mut reached = vec![members[0]];
+                let mut next = 0;
+                while next < reached.len() {
+                    let (i, j) = reached[next];
+                    next += 1;
+                    for &(ni, nj) in members {
+                        if !reached.contains(&(ni, nj)) && i.abs_diff(ni) + j.abs_diff(nj) == 1 {
+                            reached.push((ni, nj));
+                        }
+                    }
+                }
+                if reached.len() != BOARD_SIZE {
+                    return Err(Box::from(format!("Region {} isn't connected", region + 1)));
+                }
+            }
+
+            return Ok(Regions { map, cells });
+        }
+
+        pub fn region_of(&self, row: usize, col: usize) -> usize {
+            return self.map[row][col] as usize;
+        }
+
+        /// (row, col) of every cell in a region
+        pub fn cells(&self, region: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+            return self.cells[region]
+                .iter()
+                .map(|&(i, j)| (i as usize, j as usize));
+        }
+
+        pub fn is_boxes(&self) -> bool {
+            return *self == Self::boxes();
+        }
+    }
+
+    impl Default for Regions {
+        fn default() -> Self {
+            return Self::boxes();
+        }
+    }
+
+    /// Reads 81 symbols, one per cell in reading order, using nine different symbols for the
+    /// nine regions. Whitespace is ignored, so the map can be laid out as a grid.
+    impl FromStr for Regions {
+        type Err = Box<dyn std::error::Error>;
+
+        fn from_str(text: &str) -> Result<Self, Self::Err> {
+            let symbols: Vec<char> = text.chars().filter(|c| !c.is_whitespace()).collect();
+            if symbols.len() != BOARD_SIZE * BOARD_SIZE {
+                return Err(Box::from(format!(
+                    "Region map needs {} cells, found {}",
+                    BOARD_SIZE * BOARD_SIZE,
+                    symbols.len()
+                )));
+            }
+            // Regions are numbered in order of first appearance
+            let mut seen: Vec<char> = Vec::new();
+            let mut map = [[0; BOARD_SIZE]; BOARD_SIZE];
+            for (i, c) in symbols.into_iter().enumerate() {
+                let region = match seen.iter().position(|s| *s == c) {
+                    Some(region) => region,
+                    None => {
+                        seen.push(c);
+                        seen.len() - 1
+                    }
+                };
+                if region >= BOARD_SIZE {
+                    return Err(Box::from("Region map uses more than nine symbols"));
+                }
+                map[i / BOARD_SIZE][i % BOARD_SIZE] = region as u8;
+            }
+            return Self::from_map(map);
+        }
+    }
+
+    /// Writes the map as 81 digits, 1 to 9
+    impl Display for Regions {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            for region in self.map.iter().flatten() {
+                write!(f, "{}", region + 1)?;
+            }
+            Ok(())
+        }
+    }
 
     #[derive(Debug, Clone)]
     pub struct Board {
         pub(crate) grid: [[Option<i8>; BOARD_SIZE]; BOARD_SIZE],
+        pub(crate) regions: Regions,
+        /// Sudoku X: both main diagonals hold every digit too
+        pub(crate) diagonal: bool,
+        /// Extra groups of nine cells holding every digit, such as the windoku windows. They
+        /// may overlap the regions.
+        pub(crate) extra_regions: Vec<[(usize, usize); BOARD_SIZE]>,
+        /// Built-in constraints: rows, columns, regions, diagonals and extra regions, kept in
+        /// step with the fields above
+        pub(crate) houses: Vec<House>,
+        pub(crate) cages: Vec<Cage>,
+        /// Variant rules beyond houses and cages
+        pub(crate) constraints: Vec<Arc<dyn Constraint>>,
     }
 
     impl Board {
@@ -22,12 +300,15 @@ pub mod sudoku {
                 for (j, _) in arr[i].iter().enumerate() {
                     let val = arr[i][j].clone().into();
                     if val != 0i8 {
-                        grid[i][j] = Some(val.into());
+                        grid[i][j] = Some(val);
                     }
                 }
             }
 
-            return Self { grid };
+            return Self {
+                grid,
+                ..Self::default()
+            };
         }
 
         pub fn to_clean_string(&self) -> String {
@@ -55,6 +336,9 @@ pub mod sudoku {
                     "String entry contains a non-number {}",
                     c
                 );*/
+                if c == '.' {
+                    continue;
+                }
                 arr[i / BOARD_SIZE][i % BOARD_SIZE] = c
                     .to_digit(10)
                     .ok_or("Board construction from string requires only numbers in string")?
@@ -64,106 +348,314 @@ pub mod sudoku {
             return Ok(Self::from_arr_with_zeros(arr));
         }
 
-        pub fn from_solution(board: Solution) -> Self {
-            Self { grid: board.grid }
+        pub fn get(&self, row: usize, col: usize) -> Option<i8> {
+            return self.grid[row][col];
         }
 
-        pub fn combine(&self, other: &Solution) -> Result<Board, Box<dyn std::error::Error>> {
-            let mut combined = Board::default();
+        /// Digits outside 1-9 leave the cell empty, like `0` does in [`Board::from_string`]
+        pub fn set(&mut self, row: usize, col: usize, value: Option<i8>) {
+            self.grid[row][col] = value.filter(|digit| (1..=BOARD_SIZE as i8).contains(digit));
+        }
+
+        /// Number of filled cells
+        pub fn clue_count(&self) -> usize {
+            return self.grid.iter().flatten().filter(|c| c.is_some()).count();
+        }
+
+        pub fn is_complete(&self) -> bool {
+            return self.clue_count() == BOARD_SIZE * BOARD_SIZE;
+        }
+
+        pub fn regions(&self) -> &Regions {
+            return &self.regions;
+        }
 
+        /// Makes this a jigsaw board, or a standard one again with [`Regions::boxes`]
+        pub fn set_regions(&mut self, regions: Regions) {
+            self.regions = regions;
+            self.houses = self.build_houses();
+        }
+
+        pub fn is_diagonal(&self) -> bool {
+            return self.diagonal;
+        }
+
+        pub fn set_diagonal(&mut self, diagonal: bool) {
+            self.diagonal = diagonal;
+            self.houses = self.build_houses();
+        }
+
+        fn build_houses(&self) -> Vec<House> {
+            let mut houses = Vec::new();
             for i in 0..BOARD_SIZE {
-                for j in 0..BOARD_SIZE {
-                    match (self.grid[i][j], other.grid[i][j]) {
-                        (None, None) => {
-                            return Err(Box::from(format!(
-                                "Neither board nor solution have ({},{})",
-                                i, j
-                            )));
-                        }
-                        (None, Some(num)) => {
-                            combined.grid[i][j] = Some(num);
-                        }
-                        (Some(num), None) => {
-                            combined.grid[i][j] = Some(num);
-                        }
-                        (Some(_), Some(_)) => {
-                            return Err(Box::from(format!(
-                                "Both board and solution have ({},{})",
-                                i, j
-                            )));
-                        }
-                    }
+                houses.push(House::new(
+                    format!("row {}", i + 1),
+                    std::array::from_fn(|j| (i, j)),
+                ));
+            }
+            for j in 0..BOARD_SIZE {
+                houses.push(House::new(
+                    format!("column {}", j + 1),
+                    std::array::from_fn(|i| (i, j)),
+                ));
+            }
+            for region in 0..BOARD_SIZE {
+                let mut cells = self.regions.cells(region);
+                houses.push(House::new(
+                    format!("region {}", region + 1),
+                    std::array::from_fn(|_| cells.next().unwrap()),
+                ));
+            }
+            if self.diagonal {
+                houses.push(House::new("diagonal", std::array::from_fn(|i| (i, i))));
+                houses.push(House::new(
+                    "anti-diagonal",
+                    std::array::from_fn(|i| (i, BOARD_SIZE - 1 - i)),
+                ));
+            }
+            for (k, cells) in self.extra_regions.iter().enumerate() {
+                houses.push(House::new(format!("extra region {}", k + 1), *cells));
+            }
+            return houses;
+        }
+
+        pub fn extra_regions(&self) -> &[[(usize, usize); BOARD_SIZE]] {
+            return &self.extra_regions;
+        }
+
+        /// Adds nine distinct cells that must hold every digit once
+        pub fn add_extra_region(
+            &mut self,
+            cells: [(usize, usize); BOARD_SIZE],
+        ) -> Result<(), Box<dyn std::error::Error>> {
+            for (k, &(i, j)) in cells.iter().enumerate() {
+                if i >= BOARD_SIZE || j >= BOARD_SIZE {
+                    return Err(Box::from(format!(
+                        "Extra region cell is off the board: ({},{})",
+                        i, j
+                    )));
+                }
+                if cells[..k].contains(&(i, j)) {
+                    return Err(Box::from(format!("Extra region has ({},{}) twice", i, j)));
                 }
             }
+            self.extra_regions.push(cells);
+            self.houses = self.build_houses();
+            return Ok(());
+        }
 
-            return Ok(combined);
+        /// Adds the four shaded 3x3 windows of a windoku (hyper-sudoku)
+        pub fn add_windoku(&mut self) {
+            for (top, left) in WINDOKU {
+                self.extra_regions
+                    .push(std::array::from_fn(|k| (top + k / 3, left + k % 3)));
+            }
+            self.houses = self.build_houses();
         }
 
-        pub fn score_solution(&self, other: &Solution) -> Result<u32, Box<dyn std::error::Error>> {
-            let mut score: u32 = 0;
+        /// Rows, columns, regions, diagonals and extra regions: groups of nine cells holding
+        /// every digit
+        pub fn houses(&self) -> &[House] {
+            return &self.houses;
+        }
+
+        /// Pairs of filled cells holding the same digit in a row, column, region or other house,
+        /// each pair once and in reading order. Empty cells are ignored, so this works on
+        /// partly filled boards.
+        pub fn conflicts(&self) -> Vec<[(usize, usize); 2]> {
+            let mut pairs: Vec<[(usize, usize); 2]> = self
+                .houses
+                .iter()
+                .flat_map(|house| house.violations(&self.grid))
+                .map(|violation| [violation.cells[0], violation.cells[1]])
+                .map(|[a, b]| [a.min(b), a.max(b)])
+                .collect();
+            pairs.sort_unstable();
+            pairs.dedup();
+            return pairs;
+        }
+
+        /// Adds a variant rule, checked by both solvers
+        pub fn add_constraint(&mut self, constraint: impl Constraint + 'static) {
+            self.constraints.push(Arc::new(constraint));
+        }
+
+        /// Every rule of the board: houses, then cages, then other variant rules
+        pub fn constraints(&self) -> impl Iterator<Item = &dyn Constraint> {
+            let houses = self.houses.iter().map(|h| h as &dyn Constraint);
+            let cages = self.cages.iter().map(|c| c as &dyn Constraint);
+            return houses
+                .chain(cages)
+                .chain(self.constraints.iter().map(|c| c.as_ref()));
+        }
 
-            let combined = Self::combine(&self, other)?;
+        /// Rules other than houses
+        pub fn variant_constraints(&self) -> impl Iterator<Item = &dyn Constraint> {
+            return self.constraints().skip(self.houses.len());
+        }
+
+        pub fn cages(&self) -> &[Cage] {
+            return &self.cages;
+        }
 
-            // Rows
-            for x in combined.grid {
-                let mut bv = BitVec::from_elem(BOARD_SIZE, false);
-                for i in x {
-                    bv.set(unsafe { i.unwrap_unchecked() - 1 } as usize, true);
+        /// Adds a killer cage. Cages can't overlap and must have a sum their cells can reach.
+        pub fn add_cage(&mut self, cage: Cage) -> Result<(), Box<dyn std::error::Error>> {
+            let size = cage.cells.len() as u32;
+            if !(1..=BOARD_SIZE as u32).contains(&size) {
+                return Err(Box::from(format!("Cage has {} cells: {}", size, cage)));
+            }
+            // Smallest and largest sums of `size` different digits
+            let min = size * (size + 1) / 2;
+            let max = size * (19 - size) / 2;
+            if !(min..=max).contains(&cage.sum) {
+                return Err(Box::from(format!("Cage sum can't be reached: {}", cage)));
+            }
+            for (i, cell) in cage.cells.iter().enumerate() {
+                if cell.0 >= BOARD_SIZE || cell.1 >= BOARD_SIZE {
+                    return Err(Box::from(format!("Cage cell is off the board: {}", cage)));
+                }
+                let taken = cage.cells[..i].contains(cell)
+                    || self.cages.iter().any(|other| other.cells.contains(cell));
+                if taken {
+                    return Err(Box::from(format!(
+                        "({},{}) is in more than one cage",
+                        cell.0, cell.1
+                    )));
                 }
-                score += bv.count_zeros() as u32;
             }
+            self.cages.push(cage);
+            return Ok(());
+        }
+
+        /// A full board holding the solution's digits, with the solution's regions
+        pub fn from_solution(solution: Solution) -> Self {
+            let mut board = Self {
+                grid: solution.grid(),
+                ..Self::default()
+            };
+            board.set_regions(solution.regions);
+            return board;
+        }
 
-            // Columns
+        /// This board with every empty cell filled in from the solution
+        pub fn combine(&self, other: &Solution) -> Result<Board, Box<dyn std::error::Error>> {
+            self.check_solution(other)?;
+            return Ok(Board {
+                grid: other.grid(),
+                ..self.clone()
+            });
+        }
+
+        /// Fails if the solution changes one of the board's digits
+        fn check_solution(&self, other: &Solution) -> Result<(), Box<dyn std::error::Error>> {
             for i in 0..BOARD_SIZE {
-                let mut bv = BitVec::from_elem(BOARD_SIZE, false);
                 for j in 0..BOARD_SIZE {
-                    bv.set(
-                        unsafe { combined.grid[j][i].unwrap_unchecked() - 1 } as usize,
-                        true,
-                    );
-                }
-                score += bv.count_zeros() as u32;
-            }
-
-            // Blocks
-            for block_i in 0..3 {
-                for block_j in 0..3 {
-                    let mut bv = BitVec::from_elem(BOARD_SIZE, false);
-                    for i in 0..3 {
-                        for j in 0..3 {
-                            bv.set(
-                                unsafe {
-                                    combined.grid[block_i * 3 + i][block_j * 3 + j]
-                                        .unwrap_unchecked()
-                                        - 1
-                                } as usize,
-                                true,
-                            );
-                        }
+                    if let Some(num) = self.grid[i][j]
+                        && num != other.digits[i][j]
+                    {
+                        return Err(Box::from(format!(
+                            "Solution has {} at ({},{}) where the board has {}",
+                            other.digits[i][j], i, j, num
+                        )));
                     }
-                    score += bv.count_zeros() as u32;
                 }
             }
+            return Ok(());
+        }
 
+        /// Sum of every constraint's penalty, 0 for a correct solution. This runs for every
+        /// step of the annealer, so houses are scored straight from the
+        /// digits and the grid is only built for boards with other rules.
+        pub fn score_solution(&self, other: &Solution) -> Result<u32, Box<dyn std::error::Error>> {
+            self.check_solution(other)?;
+            let mut score: u32 = self.houses.iter().map(|h| h.missing(&other.digits)).sum();
+            if !self.cages.is_empty() || !self.constraints.is_empty() {
+                let grid = other.grid();
+                score += self
+                    .variant_constraints()
+                    .map(|c| c.penalty(&grid))
+                    .sum::<u32>();
+            }
             return Ok(score);
         }
     }
 
     impl Default for Board {
         fn default() -> Self {
-            Self {
+            let mut board = Self {
                 grid: [[None; BOARD_SIZE]; BOARD_SIZE],
+                regions: Regions::boxes(),
+                diagonal: false,
+                extra_regions: Vec::new(),
+                houses: Vec::new(),
+                cages: Vec::new(),
+                constraints: Vec::new(),
+            };
+            board.houses = board.build_houses();
+            return board;
+        }
+    }
+
+    /// Space in front of a cell, marking the diagonals on Sudoku X boards
+    fn cell_prefix(diagonal: bool, i: usize, j: usize) -> char {
+        let main = i == j;
+        let anti = i + j == BOARD_SIZE - 1;
+        match (diagonal, main, anti) {
+            (true, true, true) => '╳',
+            (true, true, false) => '╲',
+            (true, false, true) => '╱',
+            _ => ' ',
+        }
+    }
+
+    impl Board {
+        /// Jigsaw boards draw region borders between cells instead of the box separators
+        fn fmt_jigsaw(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            let regions = &self.regions;
+            writeln!(f, "╔═══════════════════╗")?;
+            for (i, x) in self.grid.iter().enumerate() {
+                if i > 0 {
+                    let border = |j: usize| regions.region_of(i - 1, j) != regions.region_of(i, j);
+                    let line = |on: bool| if on { '─' } else { ' ' };
+                    write!(f, "║")?;
+                    for j in 0..BOARD_SIZE {
+                        let corner = border(j) || (j > 0 && border(j - 1));
+                        write!(f, "{}{}", line(corner), line(border(j)))?;
+                    }
+                    writeln!(f, "{}║", line(border(BOARD_SIZE - 1)))?;
+                }
+                write!(f, "║")?;
+
+                for (j, y) in x.iter().enumerate() {
+                    let prefix = if j > 0 && regions.region_of(i, j - 1) != regions.region_of(i, j)
+                    {
+                        '│'
+                    } else {
+                        cell_prefix(self.diagonal, i, j)
+                    };
+                    match y {
+                        Some(num) => write!(f, "{}{}", prefix, num)?,
+                        None => write!(f, "{} ", prefix)?,
+                    }
+                }
+                writeln!(f, " ║")?;
             }
+            writeln!(f, "╚═══════════════════╝ ")?;
+
+            Ok(())
         }
     }
 
     //Display assumes 9x9 board
     impl Display for Board {
         fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
-            write!(f, "╔═══════════════════════╗\n")?;
+            if !self.regions.is_boxes() {
+                return self.fmt_jigsaw(f);
+            }
+            writeln!(f, "╔═══════════════════════╗")?;
             for (i, x) in self.grid.iter().enumerate() {
                 if i == 3 || i == 6 {
-                    write!(f, "║⋯⋯⋯⋯⋯⋯⋯⸭⋯⋯⋯⋯⋯⋯⋯⸭⋯⋯⋯⋯⋯⋯⋯║\n")?;
+                    writeln!(f, "║⋯⋯⋯⋯⋯⋯⋯⸭⋯⋯⋯⋯⋯⋯⋯⸭⋯⋯⋯⋯⋯⋯⋯║")?;
                 }
                 write!(f, "║")?;
 
@@ -171,85 +663,192 @@ pub mod sudoku {
                     if j == 3 || j == 6 {
                         write!(f, " ⁞")?;
                     }
+                    let prefix = cell_prefix(self.diagonal, i, j);
                     match y {
-                        Some(num) => write!(f, " {}", num)?,
-                        None => write!(f, "  ")?,
+                        Some(num) => write!(f, "{}{}", prefix, num)?,
+                        None => write!(f, "{} ", prefix)?,
                     }
                 }
-                write!(f, " ║\n")?;
+                writeln!(f, " ║")?;
             }
-            write!(f, "╚═══════════════════════╝ \n")?;
+            writeln!(f, "╚═══════════════════════╝ ")?;
 
             Ok(())
         }
     }
 
+    /// A candidate answer for the annealer: a digit in every cell, givens included, so scoring
+    /// never has to deal with an empty cell
     #[derive(Debug, Clone)]
     pub struct Solution {
-        pub(crate) grid: [[Option<i8>; BOARD_SIZE]; BOARD_SIZE],
+        pub(crate) digits: [[i8; BOARD_SIZE]; BOARD_SIZE],
+        /// Cells the annealer may swap, the ones that were empty on the board
+        pub(crate) free: [[bool; BOARD_SIZE]; BOARD_SIZE],
+        pub(crate) regions: Regions,
+        /// Regions with at least two free cells, as bits, the only ones a swap can change
+        pub(crate) movable: u16,
+        /// Digits each cell may take given the board's digits and rules. Swaps mostly keep
+        /// digits on cells that allow them, which spares windows and other houses that overlap
+        /// the regions from clashes with the givens.
+        pub(crate) allowed: [[u16; BOARD_SIZE]; BOARD_SIZE],
     }
 
     impl Solution {
-        pub fn from_board(board: Board) -> Self {
-            Self { grid: board.grid }
+        /// Takes every digit of a full board, all of them free to swap
+        pub fn from_board(board: Board) -> Result<Self, Box<dyn std::error::Error>> {
+            let mut digits = [[0; BOARD_SIZE]; BOARD_SIZE];
+            for (i, row) in digits.iter_mut().enumerate() {
+                for (j, digit) in row.iter_mut().enumerate() {
+                    *digit = board.grid[i][j].ok_or_else(|| {
+                        format!(
+                            "Solution needs a digit in every cell, ({},{}) is empty",
+                            i, j
+                        )
+                    })?;
+                }
+            }
+            let mut solution = Solution {
+                digits,
+                free: [[true; BOARD_SIZE]; BOARD_SIZE],
+                regions: board.regions,
+                movable: 0,
+                allowed: [[ALL_DIGITS; BOARD_SIZE]; BOARD_SIZE],
+            };
+            solution.movable = solution.movable_regions();
+            return Ok(solution);
         }
 
+        /// Keeps the board's digits and fills each region's empty cells with the digits it is
+        /// missing, in order
         pub fn new_from_board(board: &Board) -> Result<Self, Box<dyn std::error::Error>> {
-            let mut solution = Solution::default();
-
-            for block_i in 0..3 {
-                for block_j in 0..3 {
-                    // for each block, find the unused numbers ...
-                    let mut taken = [false; 9];
-                    for i in 0..3 {
-                        for j in 0..3 {
-                            let tile = board.grid[block_i * 3 + i][block_j * 3 + j];
-                            if let Some(num) = tile {
-                                if taken[(num - 1) as usize] {
-                                    return Err(Box::from(format!(
-                                        "{} is used twice in same original board block ({},{})",
-                                        num,
-                                        block_i * 3 + i,
-                                        block_j * 3 + j
-                                    )));
-                                }
-                                taken[(num - 1) as usize] = true;
-                            }
+            let mut solution = Solution {
+                regions: board.regions,
+                ..Solution::default()
+            };
+            let candidates = Candidates::from_board(board);
+
+            for region in 0..BOARD_SIZE {
+                // for each region, find the unused numbers ...
+                let mut taken = [false; 9];
+                for (i, j) in board.regions.cells(region) {
+                    if let Some(num) = board.grid[i][j] {
+                        if taken[(num - 1) as usize] {
+                            return Err(Box::from(format!(
+                                "{} is used twice in same original board block ({},{})",
+                                num, i, j
+                            )));
                         }
+                        taken[(num - 1) as usize] = true;
+                        solution.digits[i][j] = num;
+                        solution.free[i][j] = false;
                     }
-                    // ... and add them in order to the unfilled spots
-                    for i in 0..3 {
-                        for j in 0..3 {
-                            let tile = board.grid[block_i * 3 + i][block_j * 3 + j];
-                            if let Some(_) = tile {
-                            } else {
-                                solution.grid[block_i * 3 + i][block_j * 3 + j] =
-                                    Some(find_next_empty_num(&mut taken));
-                            }
-                        }
+                }
+                // ... and add them to the unfilled spots, each on a cell that allows it
+                let spots: Vec<(usize, usize)> = board
+                    .regions
+                    .cells(region)
+                    .filter(|&(i, j)| board.grid[i][j].is_none())
+                    .collect();
+                for &(i, j) in &spots {
+                    solution.allowed[i][j] = candidates.get(i, j);
+                }
+                let missing: Vec<i8> = (1..=BOARD_SIZE as i8)
+                    .filter(|&num| !taken[(num - 1) as usize])
+                    .collect();
+                if !solution.place_allowed(&spots, &missing) {
+                    // Only on boards that break their own rules, which the score will show
+                    for &(i, j) in &spots {
+                        solution.allowed[i][j] = ALL_DIGITS;
+                        solution.digits[i][j] = find_next_empty_num(&mut taken);
                     }
                 }
             }
+            solution.movable = solution.movable_regions();
 
             return Ok(solution);
         }
 
-        pub fn to_clean_string(&self) -> String {
-            let mut out = "".to_string();
-            for i in 0..9 {
-                for j in 0..9 {
-                    out += &self.grid[i][j].unwrap_or(0).to_string();
+        pub fn get(&self, row: usize, col: usize) -> i8 {
+            return self.digits[row][col];
+        }
+
+        /// Puts `digits` on `spots`, one each and only where allowed, by augmenting paths.
+        /// Returns false, changing nothing, when there is no such arrangement.
+        fn place_allowed(&mut self, spots: &[(usize, usize)], digits: &[i8]) -> bool {
+            // owner[d] is the spot digits[d] is on
+            let mut owner: Vec<Option<usize>> = vec![None; digits.len()];
+            for spot in 0..spots.len() {
+                let mut visited = vec![false; digits.len()];
+                if !self.augment(spots, digits, spot, &mut owner, &mut visited) {
+                    return false;
                 }
             }
-            return out;
+            for (d, spot) in owner.into_iter().enumerate() {
+                let (i, j) = spots[spot.expect("every spot got a digit")];
+                self.digits[i][j] = digits[d];
+            }
+            return true;
         }
 
-        pub(crate) fn flip_spaces(&mut self, x1: usize, y1: usize, x2: usize, y2: usize) {
-            unsafe {
-                let pa: *mut Option<i8> = &mut self.grid[x1][y1];
-                let pb: *mut Option<i8> = &mut self.grid[x2][y2];
-                ptr::swap(pa, pb);
+        /// Finds `spot` a digit, moving other spots to other digits if needed
+        fn augment(
+            &self,
+            spots: &[(usize, usize)],
+            digits: &[i8],
+            spot: usize,
+            owner: &mut [Option<usize>],
+            visited: &mut [bool],
+        ) -> bool {
+            let (i, j) = spots[spot];
+            for d in 0..digits.len() {
+                if visited[d] || self.allowed[i][j] & digit_bit(digits[d]) == 0 {
+                    continue;
+                }
+                visited[d] = true;
+                let free = match owner[d] {
+                    None => true,
+                    Some(other) => self.augment(spots, digits, other, owner, visited),
+                };
+                if free {
+                    owner[d] = Some(spot);
+                    return true;
+                }
+            }
+            return false;
+        }
+
+        pub(crate) fn allows(&self, (i, j): (usize, usize), digit: i8) -> bool {
+            return self.allowed[i][j] & digit_bit(digit) != 0;
+        }
+
+        fn movable_regions(&self) -> u16 {
+            let mut movable = 0;
+            for region in 0..BOARD_SIZE {
+                let free = self
+                    .regions
+                    .cells(region)
+                    .filter(|&(i, j)| self.free[i][j])
+                    .count();
+                if free >= 2 {
+                    movable |= 1 << region;
+                }
             }
+            return movable;
+        }
+
+        /// The digits in the form the constraints check
+        pub fn grid(&self) -> Grid {
+            return self.digits.map(|row| row.map(Some));
+        }
+
+        pub fn to_clean_string(&self) -> String {
+            return self.digits.iter().flatten().map(i8::to_string).collect();
+        }
+
+        pub(crate) fn flip_spaces(&mut self, x1: usize, y1: usize, x2: usize, y2: usize) {
+            let first = self.digits[x1][y1];
+            self.digits[x1][y1] = self.digits[x2][y2];
+            self.digits[x2][y2] = first;
         }
 
         //todo: maybe use faster random algorithm
@@ -258,61 +857,152 @@ pub mod sudoku {
             block_x: usize,
             block_y: usize,
         ) -> Result<(), Box<dyn std::error::Error>> {
-            let mut rng = rand::rng();
+            return self.flip_random_spaces_in_block_with(block_x, block_y, &mut rand::rng());
+        }
+
+        /// Blocks are numbered like [`Regions::boxes`], so on jigsaw boards this picks the
+        /// region numbered `block_x * 3 + block_y`
+        pub fn flip_random_spaces_in_block_with<R: Rng + ?Sized>(
+            &mut self,
+            block_x: usize,
+            block_y: usize,
+            rng: &mut R,
+        ) -> Result<(), Box<dyn std::error::Error>> {
+            return self.flip_random_spaces_in_region_with(block_x * 3 + block_y, rng);
+        }
 
+        /// Swaps two free cells of a region. The regions partition the board, so every swap
+        /// keeps each one holding its nine digits; extra regions and diagonals overlap them and
+        /// are only enforced through the score. Swaps that keep both digits allowed are
+        /// preferred.
+        pub fn flip_random_spaces_in_region_with<R: Rng + ?Sized>(
+            &mut self,
+            region: usize,
+            rng: &mut R,
+        ) -> Result<(), Box<dyn std::error::Error>> {
             let mut available_spaces = [(99, 99); 9];
             let mut num_spaces = 0;
 
-            for i in 0..3 {
-                for j in 0..3 {
-                    if self.grid[block_x * 3 + i][block_y * 3 + j].is_some() {
-                        available_spaces[num_spaces] = (block_x * 3 + i, block_y * 3 + j);
-                        num_spaces += 1;
-                    }
+            for (i, j) in self.regions.cells(region) {
+                if self.free[i][j] {
+                    available_spaces[num_spaces] = (i, j);
+                    num_spaces += 1;
                 }
             }
 
-            if num_spaces == 0 {
+            if num_spaces < 2 {
                 return Err(Box::from("No spaces in selected block"));
             }
 
-            let space_1_i = rng.random_range(0..num_spaces);
-            let space_1 = available_spaces[space_1_i].clone();
-
-            if space_1_i != num_spaces {
-                available_spaces.swap(num_spaces - 1, space_1_i);
-                num_spaces -= 1;
+            // Prefer swaps that leave both digits on cells that allow them, but not always,
+            // as some arrangements can only be reached through a swap that doesn't
+            let mut pair = ((99, 99), (99, 99));
+            for _ in 0..SWAP_DRAWS {
+                let space_1_i = rng.random_range(0..num_spaces);
+                let space_1 = available_spaces[space_1_i];
+                let mut space_2_i = rng.random_range(0..num_spaces - 1);
+                if space_2_i >= space_1_i {
+                    space_2_i += 1;
+                }
+                let space_2 = available_spaces[space_2_i];
+                pair = (space_1, space_2);
+                if self.allows(space_1, self.digits[space_2.0][space_2.1])
+                    && self.allows(space_2, self.digits[space_1.0][space_1.1])
+                {
+                    break;
+                }
             }
 
-            let space_2_i = rng.random_range(0..num_spaces);
-            let space_2 = available_spaces[space_2_i].clone();
-
-            //dbg!(space_1, space_2);
-
-            debug_assert_ne!(space_1, space_2);
-            debug_assert!(self.grid[space_1.0][space_1.1].is_some());
-            debug_assert!(self.grid[space_2.0][space_2.1].is_some());
-
+            let (space_1, space_2) = pair;
             self.flip_spaces(space_1.0, space_1.1, space_2.0, space_2.1);
 
             return Ok(());
         }
 
+        /// Swaps a free cell that repeats a digit in one of `houses` with another free cell of
+        /// its region. Meant for houses that overlap the regions, such as windoku windows,
+        /// which random swaps rarely fix. Returns false, changing nothing, when no repeated
+        /// cell can move.
+        pub fn flip_repeated_space_with<R: Rng + ?Sized>(
+            &mut self,
+            houses: &[House],
+            rng: &mut R,
+        ) -> bool {
+            let mut repeated = [[false; BOARD_SIZE]; BOARD_SIZE];
+            let mut count = 0;
+            for house in houses {
+                let mut seen: [Option<(usize, usize)>; BOARD_SIZE] = [None; BOARD_SIZE];
+                for &(i, j) in &house.cells {
+                    // Cells without a digit can't repeat one
+                    let digit = self.digits[i][j];
+                    if !(1..=BOARD_SIZE as i8).contains(&digit) {
+                        continue;
+                    }
+                    let digit = (digit - 1) as usize;
+                    let Some(first) = seen[digit] else {
+                        seen[digit] = Some((i, j));
+                        continue;
+                    };
+                    for (i, j) in [first, (i, j)] {
+                        let region = self.regions.region_of(i, j);
+                        if self.free[i][j] && self.movable & (1 << region) != 0 && !repeated[i][j] {
+                            repeated[i][j] = true;
+                            count += 1;
+                        }
+                    }
+                }
+            }
+            if count == 0 {
+                return false;
+            }
+
+            let nth = rng.random_range(0..count);
+            let (i1, j1) = (0..BOARD_SIZE * BOARD_SIZE)
+                .map(|k| (k / BOARD_SIZE, k % BOARD_SIZE))
+                .filter(|&(i, j)| repeated[i][j])
+                .nth(nth)
+                .expect("nth is below the number of repeated cells");
+            let mut others = [(99, 99); BOARD_SIZE];
+            let mut num_others = 0;
+            for (i, j) in self.regions.cells(self.regions.region_of(i1, j1)) {
+                if self.free[i][j] && (i, j) != (i1, j1) {
+                    others[num_others] = (i, j);
+                    num_others += 1;
+                }
+            }
+            let (i2, j2) = others[rng.random_range(0..num_others)];
+            self.flip_spaces(i1, j1, i2, j2);
+            return true;
+        }
+
         pub fn flip_random_spaces(&mut self) {
-            let mut rng = rand::rng();
-            let block_x = rng.random_range(0..3);
-            let block_y = rng.random_range(0..3);
-            self.flip_random_spaces_in_block(block_x, block_y)
-                .unwrap_or_else(|_| {
-                    println!("Couldn't flip spaces in full block!");
-                });
+            self.flip_random_spaces_with(&mut rand::rng());
+        }
+
+        /// Swaps two free cells of a random region that has them. Does nothing when no region
+        /// has two free cells, as then the solution is fixed.
+        pub fn flip_random_spaces_with<R: Rng + ?Sized>(&mut self, rng: &mut R) {
+            if self.movable == 0 {
+                return;
+            }
+            let nth = rng.random_range(0..self.movable.count_ones());
+            let region = (0..BOARD_SIZE)
+                .filter(|region| self.movable & (1 << region) != 0)
+                .nth(nth as usize)
+                .expect("nth is below the number of movable regions");
+            self.flip_random_spaces_in_region_with(region, rng)
+                .expect("movable regions have two free cells");
         }
     }
 
     impl Default for Solution {
         fn default() -> Self {
             Self {
-                grid: [[None; BOARD_SIZE]; BOARD_SIZE],
+                digits: [[0; BOARD_SIZE]; BOARD_SIZE],
+                free: [[true; BOARD_SIZE]; BOARD_SIZE],
+                regions: Regions::boxes(),
+                movable: (1 << BOARD_SIZE) - 1,
+                allowed: [[ALL_DIGITS; BOARD_SIZE]; BOARD_SIZE],
             }
         }
     }
@@ -325,9 +1015,9 @@ pub mod sudoku {
     }
 
     fn find_next_empty_num(taken: &mut [bool; 9]) -> i8 {
-        for i in 0..9 {
-            if !taken[i] {
-                taken[i] = true;
+        for (i, slot) in taken.iter_mut().enumerate() {
+            if !*slot {
+                *slot = true;
                 return (i + 1) as i8;
             }
         }
@@ -337,17 +1027,27 @@ pub mod sudoku {
 
 #[cfg(test)]
 mod tests {
-    use crate::Board;
-    use crate::Solution;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use std::time::Instant;
+
+    use crate::solver::solver::{OVERLAP_TIME_LIMIT, SolveOptions, solve_board_with_options};
+    use crate::sudoku::sudoku::{Board, Cage, Regions, Solution};
+
+    const JIGSAW: &str =
+        "412223333411122233411122663441526633441555666445585996777785996777785999788888899";
     #[test]
     fn correct_solution() {
         let board = Board::from_string(
             "278000401609100050005006900430809000706003000091000800000020173860001004107934685",
         )
         .unwrap();
-        let solution = Solution::from_board(Board::from_string(
-            "000395060040082307310740028002050716080210549500467032954608000003570290020000000",
-        ))
+        let solution = Solution::from_board(
+            Board::from_string(
+                "278395461649182357315746928432859716786213549591467832954628173863571294127934685",
+            )
+            .unwrap(),
+        )
         .unwrap();
 
         let score = board.score_solution(&solution).unwrap();
@@ -361,9 +1061,12 @@ mod tests {
             "278000401609100050005006900430809000706003000091000800000020173860001004107934685",
         )
         .unwrap();
-        let solution = Solution::from_board(Board::from_string(
-            "000495060040082307310740028002050716080210549500467032954608000003570290020000000",
-        ))
+        let solution = Solution::from_board(
+            Board::from_string(
+                "278495461649182357315746928432859716786213549591467832954628173863571294127934685",
+            )
+            .unwrap(),
+        )
         .unwrap();
 
         let score = board.score_solution(&solution).unwrap();
@@ -377,9 +1080,12 @@ mod tests {
             "278000401609100050005006900430809000706003000091000800000020173860001004107934685",
         )
         .unwrap();
-        let solution = Solution::from_board(Board::from_string(
-            "000395060040082307310740028002050716080210549500467032954608000003570290020000003",
-        ))
+        let solution = Solution::from_board(
+            Board::from_string(
+                "278395461649182357315746928432859716786213549591467832954628173863571294127934683",
+            )
+            .unwrap(),
+        )
         .unwrap();
 
         let score = board.score_solution(&solution);
@@ -389,12 +1095,232 @@ mod tests {
 
     #[test]
     fn test_flip() {
-        let mut solution = Solution::from_board(Board::from_string(
-            "123456789123456789123456789123456789123456789123456789123456789123456789123456789",
-        ));
+        let mut solution = Solution::from_board(
+            Board::from_string(
+                "123456789123456789123456789123456789123456789123456789123456789123456789123456789",
+            )
+            .unwrap(),
+        )
+        .unwrap();
 
         solution.flip_spaces(0, 0, 0, 1);
-        assert_eq!(solution.grid[0][0], Some(2));
-        assert_eq!(solution.grid[0][1], Some(1));
+        assert_eq!(solution.get(0, 0), 2);
+        assert_eq!(solution.get(0, 1), 1);
+    }
+
+    #[test]
+    fn scores_cages() {
+        let mut board = Board::from_string(
+            "278000401609100050005006900430809000706003000091000800000020173860001004107934685",
+        )
+        .unwrap();
+        board.add_cage("10 r1c4 r1c5".parse().unwrap()).unwrap();
+        board.add_cage("6 r2c2 r2c3".parse().unwrap()).unwrap();
+        assert!(board.add_cage("8 r1c5 r1c6".parse().unwrap()).is_err());
+        assert!(board.add_cage("2 r5c5 r5c6".parse().unwrap()).is_err());
+        assert!("10 r0c4".parse::<Cage>().is_err());
+
+        let solution = Solution::from_board(
+            Board::from_string(
+                "278395461649182357315746928432859716786213549591467832954628173863571294127934685",
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        // r1c4 + r1c5 is 12, two off; r2c2 + r2c3 is 4 + 9, seven off
+        assert_eq!(board.score_solution(&solution).unwrap(), 9);
+    }
+
+    #[test]
+    fn scores_and_marks_diagonals() {
+        // Valid as a plain sudoku, but both diagonals repeat digits
+        let solved = Board::from_string(
+            "278395461649182357315746928432859716786213549591467832954628173863571294127934685",
+        )
+        .unwrap();
+        let mut board = Board::default();
+        let solution = Solution::from_board(solved.clone()).unwrap();
+        assert_eq!(board.score_solution(&solution).unwrap(), 0);
+
+        board.set_diagonal(true);
+        assert!(board.score_solution(&solution).unwrap() > 0);
+
+        // Cells without a digit score as empty rather than as a digit
+        let blank = Solution::default();
+        assert_eq!(board.score_solution(&blank).unwrap(), 29 * 9);
+
+        let mut marked = solved;
+        marked.set_diagonal(true);
+        let text = marked.to_string();
+        assert!(text.contains("║╲2 7 8 ⁞ 3 9 5 ⁞ 4 6╱1 ║"));
+        assert!(text.contains("║ 7 8 6 ⁞ 2╳1 3 ⁞ 5 4 9 ║"));
+    }
+
+    #[test]
+    fn scores_extra_regions() {
+        let windoku = Board::from_string(
+            "123456789456789123789123456534297861261834597897561234912378645348615972675942318",
+        )
+        .unwrap();
+        let mut board = Board::default();
+        board.add_windoku();
+        assert_eq!(board.houses().len(), 31);
+        assert_eq!(board.extra_regions()[3][8], (7, 7));
+        let solution = Solution::from_board(windoku).unwrap();
+        assert_eq!(board.score_solution(&solution).unwrap(), 0);
+
+        // Every cell but the first comes up twice
+        board
+            .add_extra_region(std::array::from_fn(|j| (0, j / 2)))
+            .unwrap_err();
+        // A broken diagonal, which the solution repeats digits on
+        board
+            .add_extra_region(std::array::from_fn(|k| (k, (k + 1) % 9)))
+            .unwrap();
+        assert!(board.score_solution(&solution).unwrap() > 0);
+    }
+
+    #[test]
+    fn anneals_windoku() {
+        // Only the windows make this one unique, see the exact solver's test
+        let mut board = Board::from_string(
+            "020400700000089003000000400500097000000000000807560000000008600000010000000000010",
+        )
+        .unwrap();
+        board.add_windoku();
+        let solution = Solution::new_from_board(&board).unwrap();
+        for (i, j) in (0..81).map(|k| (k / 9, k % 9)) {
+            assert!(solution.allows((i, j), solution.get(i, j)));
+        }
+        // A blank solution has nothing repeated
+        let mut rng = StdRng::seed_from_u64(7);
+        assert!(!Solution::default().flip_repeated_space_with(board.houses(), &mut rng));
+
+        // Without a time limit of its own the board gets OVERLAP_TIME_LIMIT
+        let options = SolveOptions {
+            seed: Some(7),
+            ..SolveOptions::default()
+        };
+        let start = Instant::now();
+        assert_eq!(
+            solve_board_with_options(&board, &options)
+                .unwrap()
+                .to_clean_string(),
+            "123456789456789123789123456534297861261834597897561234912378645348615972675942318"
+        );
+        assert!(start.elapsed() < OVERLAP_TIME_LIMIT);
+    }
+
+    #[test]
+    fn finds_conflicts() {
+        // 5 repeats in the first row and box, 7 in the first column
+        let mut board = Board::from_string(
+            "550000000000000000000000000700000000000000000000000000700000000000000000000000000",
+        )
+        .unwrap();
+        assert_eq!(board.conflicts(), vec![[(0, 0), (0, 1)], [(3, 0), (6, 0)]]);
+        board.set(0, 1, None);
+        board.set(8, 8, Some(5));
+        assert_eq!(board.conflicts(), vec![[(3, 0), (6, 0)]]);
+        board.set_diagonal(true);
+        assert_eq!(board.conflicts(), vec![[(0, 0), (8, 8)], [(3, 0), (6, 0)]]);
+    }
+
+    #[test]
+    fn validates_regions() {
+        let regions: Regions = JIGSAW.parse().unwrap();
+        assert_eq!(regions.to_string().parse::<Regions>().unwrap(), regions);
+        assert_eq!(regions.region_of(0, 0), 0);
+        assert_eq!(regions.region_of(0, 1), 1);
+        assert!(!regions.is_boxes());
+        assert!(Regions::boxes().is_boxes());
+
+        // Ten symbols, a region of ten cells, and a region split in two
+        assert!(JIGSAW.replacen('4', "0", 1).parse::<Regions>().is_err());
+        assert!(JIGSAW.replacen('2', "1", 1).parse::<Regions>().is_err());
+        let mut split = JIGSAW.to_string();
+        split.replace_range(0..1, "9");
+        split.replace_range(80..81, "4");
+        assert!(split.parse::<Regions>().is_err());
+
+        // Messages number regions from 1, like the rest of the crate
+        let mut map = [[0; 9]; 9];
+        map[0][0] = 9;
+        let e = Regions::from_map(map).unwrap_err();
+        assert_eq!(e.to_string(), "Region 10 doesn't exist");
+        let e = Regions::from_map([[0; 9]; 9]).unwrap_err();
+        assert_eq!(e.to_string(), "Region 1 has more than 9 cells");
+    }
+
+    #[test]
+    fn fills_and_scores_jigsaw_regions() {
+        let mut board = Board::from_string(
+            "020450709078061004001000402700092000000100000306245100050024800000039000007008020",
+        )
+        .unwrap();
+        board.set_regions(JIGSAW.parse().unwrap());
+
+        let mut solution = Solution::new_from_board(&board).unwrap();
+        let mut rng = rand::rng();
+        for _ in 0..50 {
+            solution.flip_random_spaces_with(&mut rng);
+        }
+        // Filling and swapping only ever happen within a region, so those never repeat
+        let combined = board.combine(&solution).unwrap();
+        for region in 0..9 {
+            let mut digits: Vec<i8> = board
+                .regions()
+                .cells(region)
+                .map(|(i, j)| combined.get(i, j).unwrap())
+                .collect();
+            digits.sort();
+            assert_eq!(digits, (1..=9).collect::<Vec<i8>>());
+        }
+
+        let solved = Board::from_string(
+            "123456789578961234931687452764892315245173968386245197659324871812739546497518623",
+        )
+        .unwrap();
+        let mut empty = Board::default();
+        assert!(
+            empty
+                .score_solution(&Solution::from_board(solved.clone()).unwrap())
+                .unwrap()
+                > 0
+        );
+        empty.set_regions(JIGSAW.parse().unwrap());
+        assert_eq!(
+            empty
+                .score_solution(&Solution::from_board(solved).unwrap())
+                .unwrap(),
+            0
+        );
+    }
+
+    #[test]
+    fn only_swaps_regions_with_room() {
+        use rand::SeedableRng;
+        use rand::rngs::StdRng;
+
+        let board = Board::from_string(
+            "534678912672195348198342567800060003400803001700020006060000280000419005000080079",
+        )
+        .unwrap();
+        let mut solution = Solution::new_from_board(&board).unwrap();
+        let mut rng = StdRng::seed_from_u64(1);
+        for _ in 0..200 {
+            solution.flip_random_spaces_with(&mut rng);
+        }
+        assert!(
+            solution
+                .to_clean_string()
+                .starts_with("534678912672195348198342567")
+        );
+
+        // Nothing is free on a full board
+        let full = Board::from_string(&solution.to_clean_string()).unwrap();
+        let mut fixed = Solution::new_from_board(&full).unwrap();
+        fixed.flip_random_spaces_with(&mut rng);
+        assert_eq!(fixed.to_clean_string(), solution.to_clean_string());
     }
 }
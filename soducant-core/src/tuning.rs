@@ -0,0 +1,152 @@
+pub mod tuning {
+    use crate::solver::solver::{Solver, SolverParams};
+
+    use std::fmt::{Display, Formatter};
+    use std::time::{Duration, Instant};
+
+    /// Values to try for each annealing parameter. Every combination is evaluated.
+    #[derive(Debug, Clone)]
+    pub struct TuningSpace {
+        pub temperatures: Vec<f64>,
+        pub decays: Vec<f64>,
+        pub reheat_thresholds: Vec<u32>,
+        pub reheat_strengths: Vec<u32>,
+    }
+
+    impl Default for TuningSpace {
+        fn default() -> Self {
+            TuningSpace {
+                temperatures: vec![5.0, 9.0, 12.0],
+                decays: vec![0.97, 0.98, 0.99],
+                reheat_thresholds: vec![500, 750, 1000],
+                reheat_strengths: vec![50, 100, 200],
+            }
+        }
+    }
+
+    impl TuningSpace {
+        pub fn candidates(&self) -> Vec<SolverParams> {
+            let mut out = Vec::new();
+            for &temperature in &self.temperatures {
+                for &decay in &self.decays {
+                    for &reheat_threshold in &self.reheat_thresholds {
+                        for &reheat_strength in &self.reheat_strengths {
+                            out.push(SolverParams {
+                                temperature,
+                                decay,
+                                reheat_threshold,
+                                reheat_strength,
+                            });
+                        }
+                    }
+                }
+            }
+            return out;
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    pub struct TuningResult {
+        pub params: SolverParams,
+        pub solved: usize,
+        pub attempts: usize,
+        pub duration: Duration,
+    }
+
+    impl TuningResult {
+        pub fn solve_rate(&self) -> f64 {
+            if self.attempts == 0 {
+                return 0.0;
+            }
+            return self.solved as f64 / self.attempts as f64;
+        }
+
+        /// The metric results are ranked by
+        pub fn solves_per_second(&self) -> f64 {
+            let secs = self.duration.as_secs_f64();
+            if secs == 0.0 {
+                return 0.0;
+            }
+            return self.solved as f64 / secs;
+        }
+    }
+
+    impl Display for TuningResult {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            write!(
+                f,
+                "temperature {:.2}, decay {:.3}, reheat {} steps x{}: {}/{} ({:.1}%) in {:?}, {:.2} solves/s",
+                self.params.temperature,
+                self.params.decay,
+                self.params.reheat_threshold,
+                self.params.reheat_strength,
+                self.solved,
+                self.attempts,
+                100.0 * self.solve_rate(),
+                self.duration,
+                self.solves_per_second()
+            )
+        }
+    }
+
+    /// Runs the annealer once on every puzzle with the given parameters
+    pub fn evaluate(puzzles: &[&str], params: &SolverParams, max_iter: i32) -> TuningResult {
+        let mut solved = 0;
+        let start = Instant::now();
+
+        for puzzle in puzzles {
+            let mut solver = Solver::new_with_solver_params(puzzle, params);
+            if solver.solve(max_iter).is_some() {
+                solved += 1;
+            }
+        }
+
+        return TuningResult {
+            params: *params,
+            solved,
+            attempts: puzzles.len(),
+            duration: start.elapsed(),
+        };
+    }
+
+    /// Evaluates every candidate in `space` and returns the results, best first
+    pub fn tune(puzzles: &[&str], space: &TuningSpace, max_iter: i32) -> Vec<TuningResult> {
+        let mut results: Vec<TuningResult> = space
+            .candidates()
+            .iter()
+            .map(|params| evaluate(puzzles, params, max_iter))
+            .collect();
+
+        results.sort_by(|a, b| b.solves_per_second().total_cmp(&a.solves_per_second()));
+
+        return results;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::solver::solver::test_puzzles;
+    use crate::tuning::tuning::{TuningSpace, tune};
+
+    #[test]
+    fn tune_ranks_every_candidate() {
+        let puzzles: Vec<&str> = test_puzzles().take(3).collect();
+        let space = TuningSpace {
+            temperatures: vec![9.0],
+            decays: vec![0.98, 0.99],
+            reheat_thresholds: vec![750],
+            reheat_strengths: vec![50, 100],
+        };
+
+        let results = tune(&puzzles, &space, 20000);
+
+        assert_eq!(results.len(), 4);
+        for pair in results.windows(2) {
+            assert!(pair[0].solves_per_second() >= pair[1].solves_per_second());
+        }
+        for result in &results {
+            assert_eq!(result.attempts, 3);
+            assert!(result.solved <= result.attempts);
+        }
+    }
+}
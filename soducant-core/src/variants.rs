@@ -0,0 +1,1371 @@
+pub mod variants {
+    use std::fmt::{Display, Formatter};
+    use std::str::FromStr;
+
+    use crate::constraint::constraint::{
+        ALL_DIGITS, Candidates, Constraint, Grid, Violation, digit_bit, digits_between,
+        highest_digit, lowest_digit, parse_cell,
+    };
+    use crate::sudoku::sudoku::BOARD_SIZE;
+
+    /// Half of a knight's moves, so each pair of cells is only visited once
+    const KNIGHT_MOVES: [(isize, isize); 4] = [(1, -2), (1, 2), (2, -1), (2, 1)];
+    /// Half of a king's moves
+    const KING_MOVES: [(isize, isize); 4] = [(0, 1), (1, -1), (1, 0), (1, 1)];
+
+    /// Pairs of cells one of `moves` apart
+    fn move_pairs(
+        moves: &'static [(isize, isize)],
+    ) -> impl Iterator<Item = ((usize, usize), (usize, usize))> {
+        return (0..BOARD_SIZE * BOARD_SIZE).flat_map(move |cell| {
+            let (i, j) = (cell / BOARD_SIZE, cell % BOARD_SIZE);
+            moves.iter().filter_map(move |&(di, dj)| {
+                let (i2, j2) = (i.checked_add_signed(di)?, j.checked_add_signed(dj)?);
+                if i2 < BOARD_SIZE && j2 < BOARD_SIZE {
+                    return Some(((i, j), (i2, j2)));
+                }
+                return None;
+            })
+        });
+    }
+
+    fn repeated_pairs(
+        moves: &'static [(isize, isize)],
+        grid: &Grid,
+    ) -> impl Iterator<Item = ((usize, usize), (usize, usize))> {
+        return move_pairs(moves).filter(|&((i1, j1), (i2, j2))| {
+            grid[i1][j1].is_some() && grid[i1][j1] == grid[i2][j2]
+        });
+    }
+
+    fn move_violations(
+        rule: &str,
+        moves: &'static [(isize, isize)],
+        grid: &Grid,
+    ) -> Vec<Violation> {
+        return repeated_pairs(moves, grid)
+            .map(|(a, b)| Violation {
+                rule: rule.to_string(),
+                cells: vec![a, b],
+            })
+            .collect();
+    }
+
+    /// Each filled cell rules its digit out of the cells a move away
+    fn move_propagate(moves: &'static [(isize, isize)], grid: &Grid, candidates: &mut Candidates) {
+        for ((i1, j1), (i2, j2)) in move_pairs(moves) {
+            match (grid[i1][j1], grid[i2][j2]) {
+                (Some(num), None) => candidates.eliminate(i2, j2, num),
+                (None, Some(num)) => candidates.eliminate(i1, j1, num),
+                _ => {}
+            }
+        }
+    }
+
+    /// Equal digits may not be a knight's move apart
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct AntiKnight;
+
+    impl Constraint for AntiKnight {
+        fn violations(&self, grid: &Grid) -> Vec<Violation> {
+            return move_violations("anti-knight", &KNIGHT_MOVES, grid);
+        }
+
+        /// Number of equal pairs a knight's move apart
+        fn penalty(&self, grid: &Grid) -> u32 {
+            return repeated_pairs(&KNIGHT_MOVES, grid).count() as u32;
+        }
+
+        fn propagate(&self, grid: &Grid, candidates: &mut Candidates) {
+            move_propagate(&KNIGHT_MOVES, grid, candidates);
+        }
+
+        fn is_violated(&self, grid: &Grid) -> bool {
+            return repeated_pairs(&KNIGHT_MOVES, grid).next().is_some();
+        }
+
+        fn rule(&self) -> Option<String> {
+            return Some("anti-knight".to_string());
+        }
+    }
+
+    /// Equal digits may not touch, not even diagonally
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct AntiKing;
+
+    impl Constraint for AntiKing {
+        fn violations(&self, grid: &Grid) -> Vec<Violation> {
+            return move_violations("anti-king", &KING_MOVES, grid);
+        }
+
+        /// Number of equal pairs a king's move apart
+        fn penalty(&self, grid: &Grid) -> u32 {
+            return repeated_pairs(&KING_MOVES, grid).count() as u32;
+        }
+
+        fn propagate(&self, grid: &Grid, candidates: &mut Candidates) {
+            move_propagate(&KING_MOVES, grid, candidates);
+        }
+
+        fn is_violated(&self, grid: &Grid) -> bool {
+            return repeated_pairs(&KING_MOVES, grid).next().is_some();
+        }
+
+        fn rule(&self) -> Option<String> {
+            return Some("anti-king".to_string());
+        }
+    }
+
+    /// Candidates of a cell, or just its digit once filled
+    fn mask_of(grid: &Grid, candidates: &Candidates, (i, j): (usize, usize)) -> u16 {
+        return match grid[i][j] {
+            Some(num) => digit_bit(num),
+            None => candidates.get(i, j),
+        };
+    }
+
+    /// Checks that `cells` are on the board, distinct and each touching the one before
+    fn check_path(what: &str, cells: &[(usize, usize)]) -> Result<(), String> {
+        for (k, &(i, j)) in cells.iter().enumerate() {
+            if i >= BOARD_SIZE || j >= BOARD_SIZE {
+                return Err(format!("{} cell is off the board: ({},{})", what, i, j));
+            }
+            if cells[..k].contains(&(i, j)) {
+                return Err(format!("{} visits ({},{}) twice", what, i, j));
+            }
+            if let Some(&(pi, pj)) = k.checked_sub(1).map(|p| &cells[p])
+                && (pi.abs_diff(i) > 1 || pj.abs_diff(j) > 1)
+            {
+                return Err(format!(
+                    "{} cells ({},{}) and ({},{}) don't touch",
+                    what, pi, pj, i, j
+                ));
+            }
+        }
+        return Ok(());
+    }
+
+    fn write_cells(f: &mut Formatter<'_>, cells: &[(usize, usize)]) -> std::fmt::Result {
+        for (k, (row, col)) in cells.iter().enumerate() {
+            let sep = if k == 0 { "" } else { " " };
+            write!(f, "{}r{}c{}", sep, row + 1, col + 1)?;
+        }
+        Ok(())
+    }
+
+    /// Digits strictly increase from the bulb along the path
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Thermometer {
+        cells: Vec<(usize, usize)>,
+    }
+
+    impl Thermometer {
+        /// `cells` start at the bulb and follow the path, each touching the one before
+        pub fn new(cells: Vec<(usize, usize)>) -> Result<Self, Box<dyn std::error::Error>> {
+            if !(2..=BOARD_SIZE).contains(&cells.len()) {
+                return Err(Box::from(format!(
+                    "Thermometer has {} cells, needs 2 to 9",
+                    cells.len()
+                )));
+            }
+            check_path("Thermometer", &cells)?;
+            return Ok(Thermometer { cells });
+        }
+
+        pub fn cells(&self) -> &[(usize, usize)] {
+            return &self.cells;
+        }
+    }
+
+    impl Constraint for Thermometer {
+        /// Pairs of filled cells too close in value for the steps between them
+        fn violations(&self, grid: &Grid) -> Vec<Violation> {
+            let mut out = Vec::new();
+            for (a, &(i1, j1)) in self.cells.iter().enumerate() {
+                for (b, &(i2, j2)) in self.cells.iter().enumerate().skip(a + 1) {
+                    if let (Some(low), Some(high)) = (grid[i1][j1], grid[i2][j2])
+                        && ((high - low) as isize) < (b - a) as isize
+                    {
+                        out.push(Violation {
+                            rule: "thermometer".to_string(),
+                            cells: vec![(i1, j1), (i2, j2)],
+                        });
+                    }
+                }
+            }
+            return out;
+        }
+
+        /// How far each step falls short of going up by one
+        fn penalty(&self, grid: &Grid) -> u32 {
+            return self
+                .cells
+                .windows(2)
+                .filter_map(|pair| {
+                    let (low, high) = (grid[pair[0].0][pair[0].1]?, grid[pair[1].0][pair[1].1]?);
+                    return Some((low - high + 1).max(0) as u32);
+                })
+                .sum();
+        }
+
+        /// Each cell must be above the smallest digit the cell before can take and below the
+        /// largest the cell after can take
+        fn propagate(&self, grid: &Grid, candidates: &mut Candidates) {
+            let mut masks: Vec<u16> = self
+                .cells
+                .iter()
+                .map(|&cell| mask_of(grid, candidates, cell))
+                .collect();
+            let mut low = 0;
+            for mask in masks.iter_mut() {
+                *mask &= digits_between(low + 1, 9);
+                low = lowest_digit(*mask);
+            }
+            let mut high = 10;
+            for mask in masks.iter_mut().rev() {
+                *mask &= digits_between(1, high - 1);
+                high = highest_digit(*mask);
+            }
+            for (&(i, j), &mask) in self.cells.iter().zip(&masks) {
+                if grid[i][j].is_none() {
+                    candidates.retain(i, j, mask);
+                }
+            }
+        }
+
+        fn rule(&self) -> Option<String> {
+            return Some(format!("thermo {}", self));
+        }
+    }
+
+    impl FromStr for Thermometer {
+        type Err = Box<dyn std::error::Error>;
+
+        /// Cells from the bulb, e.g. `r1c1 r1c2 r2c3`
+        fn from_str(line: &str) -> Result<Self, Self::Err> {
+            let cells = line
+                .split_whitespace()
+                .map(parse_cell)
+                .collect::<Result<Vec<_>, String>>()?;
+            return Thermometer::new(cells);
+        }
+    }
+
+    impl Display for Thermometer {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            return write_cells(f, &self.cells);
+        }
+    }
+
+    /// The digit in the circle is the sum of the digits along the arrow. Digits on the arrow
+    /// may repeat unless a house forbids it.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Arrow {
+        circle: (usize, usize),
+        cells: Vec<(usize, usize)>,
+    }
+
+    impl Arrow {
+        /// `cells` start next to the circle and follow the arrow
+        pub fn new(
+            circle: (usize, usize),
+            cells: Vec<(usize, usize)>,
+        ) -> Result<Self, Box<dyn std::error::Error>> {
+            if !(1..BOARD_SIZE).contains(&cells.len()) {
+                return Err(Box::from(format!(
+                    "Arrow has {} cells, needs 1 to 8",
+                    cells.len()
+                )));
+            }
+            let path: Vec<(usize, usize)> = std::iter::once(circle).chain(cells.clone()).collect();
+            check_path("Arrow", &path)?;
+            return Ok(Arrow { circle, cells });
+        }
+
+        pub fn circle(&self) -> (usize, usize) {
+            return self.circle;
+        }
+
+        pub fn cells(&self) -> &[(usize, usize)] {
+            return &self.cells;
+        }
+
+        /// Sum of the filled arrow cells and how many are still empty
+        fn tally(&self, grid: &Grid) -> (i32, i32) {
+            let mut total = 0;
+            let mut empty = 0;
+            for &(i, j) in &self.cells {
+                match grid[i][j] {
+                    Some(num) => total += num as i32,
+                    None => empty += 1,
+                }
+            }
+            return (total, empty);
+        }
+    }
+
+    impl Constraint for Arrow {
+        /// The arrow already adds up to more than the circle allows, or to a different total
+        /// once it is full
+        fn violations(&self, grid: &Grid) -> Vec<Violation> {
+            let (total, empty) = self.tally(grid);
+            let circle = grid[self.circle.0][self.circle.1].map_or(BOARD_SIZE as i32, i32::from);
+            let full = empty == 0 && grid[self.circle.0][self.circle.1].is_some();
+            if total + empty > circle || (full && total != circle) {
+                return vec![Violation {
+                    rule: "arrow".to_string(),
+                    cells: std::iter::once(self.circle)
+                        .chain(self.cells.iter().copied())
+                        .filter(|&(i, j)| grid[i][j].is_some())
+                        .collect(),
+                }];
+            }
+            return Vec::new();
+        }
+
+        /// Difference between the circle and the sum along the arrow
+        fn penalty(&self, grid: &Grid) -> u32 {
+            let circle = grid[self.circle.0][self.circle.1].map_or(0, i32::from);
+            return (self.tally(grid).0 - circle).unsigned_abs();
+        }
+
+        /// Keeps the circle between the smallest and largest sums the arrow can make, and each
+        /// arrow cell small enough (and big enough) for the rest of the arrow to fit
+        fn propagate(&self, grid: &Grid, candidates: &mut Candidates) {
+            let masks: Vec<u16> = self
+                .cells
+                .iter()
+                .map(|&cell| mask_of(grid, candidates, cell))
+                .collect();
+            let min: i32 = masks.iter().map(|&mask| lowest_digit(mask)).sum();
+            let max: i32 = masks.iter().map(|&mask| highest_digit(mask)).sum();
+
+            let circle = mask_of(grid, candidates, self.circle) & digits_between(min, max);
+            if grid[self.circle.0][self.circle.1].is_none() {
+                candidates.retain(self.circle.0, self.circle.1, circle);
+            }
+            for (&(i, j), &mask) in self.cells.iter().zip(&masks) {
+                if grid[i][j].is_none() {
+                    let lo = lowest_digit(circle) - (max - highest_digit(mask));
+                    let hi = highest_digit(circle) - (min - lowest_digit(mask));
+                    candidates.retain(i, j, digits_between(lo, hi));
+                }
+            }
+        }
+
+        fn rule(&self) -> Option<String> {
+            return Some(format!("arrow {}", self));
+        }
+    }
+
+    impl FromStr for Arrow {
+        type Err = Box<dyn std::error::Error>;
+
+        /// The circle, then the arrow from the cell next to it, e.g. `r1c1 r2c2 r3c3`
+        fn from_str(line: &str) -> Result<Self, Self::Err> {
+            let mut cells = line
+                .split_whitespace()
+                .map(parse_cell)
+                .collect::<Result<Vec<_>, String>>()?;
+            if cells.is_empty() {
+                return Err(Box::from("Arrow needs a circle"));
+            }
+            let circle = cells.remove(0);
+            return Arrow::new(circle, cells);
+        }
+    }
+
+    impl Display for Arrow {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            write!(f, "r{}c{} ", self.circle.0 + 1, self.circle.1 + 1)?;
+            return write_cells(f, &self.cells);
+        }
+    }
+
+    /// A row or column, counting from 0
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Line {
+        Row(usize),
+        Column(usize),
+    }
+
+    impl Line {
+        pub fn cells(&self) -> [(usize, usize); BOARD_SIZE] {
+            return match *self {
+                Line::Row(i) => std::array::from_fn(|j| (i, j)),
+                Line::Column(j) => std::array::from_fn(|i| (i, j)),
+            };
+        }
+    }
+
+    /// Smallest and largest sums of `count` different digits from 2 to 8
+    fn sandwich_range(count: usize) -> (u32, u32) {
+        let count = count as u32;
+        return (count * (count + 3) / 2, count * (17 - count) / 2);
+    }
+
+    /// The digits between the 1 and the 9 of a row or column add up to `sum`
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Sandwich {
+        line: Line,
+        sum: u32,
+    }
+
+    impl Sandwich {
+        pub fn new(line: Line, sum: u32) -> Result<Self, Box<dyn std::error::Error>> {
+            let (Line::Row(index) | Line::Column(index)) = line;
+            if index >= BOARD_SIZE {
+                return Err(Box::from(format!(
+                    "Sandwich line is off the board: {}",
+                    index
+                )));
+            }
+            // 2 + 3 + ... + 8
+            if sum > sandwich_range(7).1 {
+                return Err(Box::from(format!("Sandwich sum can't be reached: {}", sum)));
+            }
+            return Ok(Sandwich { line, sum });
+        }
+
+        pub fn line(&self) -> Line {
+            return self.line;
+        }
+
+        pub fn sum(&self) -> u32 {
+            return self.sum;
+        }
+
+        /// Positions of the 1 and the 9 along the line, once both are placed
+        fn crusts(&self, grid: &Grid) -> Option<(usize, usize)> {
+            let cells = self.line.cells();
+            let find = |digit| cells.iter().position(|&(i, j)| grid[i][j] == Some(digit));
+            return Some((find(1)?, find(9)?));
+        }
+
+        /// Whether the empty cells of `filling` can take different digits from `mask_at`,
+        /// none of them a 1 or 9, that bring the filling up to the sum
+        fn fits(
+            &self,
+            grid: &Grid,
+            filling: &[(usize, usize)],
+            mask_at: impl Fn((usize, usize)) -> u16,
+        ) -> bool {
+            let mut total = 0;
+            let mut used = 0;
+            let mut masks = Vec::new();
+            for &(i, j) in filling {
+                match grid[i][j] {
+                    Some(num) => {
+                        total += num as i32;
+                        used |= digit_bit(num);
+                    }
+                    None => masks.push(mask_at((i, j))),
+                }
+            }
+            return can_fill(&masks, used, self.sum as i32 - total);
+        }
+    }
+
+    /// Whether cells with these candidates can take different digits from 2 to 8, avoiding
+    /// `used`, that add up to `target`
+    fn can_fill(masks: &[u16], used: u16, target: i32) -> bool {
+        let Some((&first, rest)) = masks.split_first() else {
+            return target == 0;
+        };
+        let cells = masks.len() as i32;
+        if target < 2 * cells || target > 8 * cells {
+            return false;
+        }
+        let mut options = first & !used & digits_between(2, 8);
+        while options != 0 {
+            let digit = lowest_digit(options);
+            options &= options - 1;
+            if can_fill(rest, used | (1 << (digit - 1)), target - digit) {
+                return true;
+            }
+        }
+        return false;
+    }
+
+    /// Cells strictly between positions `a` and `b` of a line
+    fn between(cells: &[(usize, usize)], a: usize, b: usize) -> &[(usize, usize)] {
+        return &cells[a.min(b) + 1..a.max(b)];
+    }
+
+    impl Constraint for Sandwich {
+        fn violations(&self, grid: &Grid) -> Vec<Violation> {
+            let Some((one, nine)) = self.crusts(grid) else {
+                return Vec::new();
+            };
+            let cells = self.line.cells();
+            if !self.fits(grid, between(&cells, one, nine), |_| ALL_DIGITS) {
+                return vec![Violation {
+                    rule: "sandwich".to_string(),
+                    cells: vec![cells[one], cells[nine]],
+                }];
+            }
+            return Vec::new();
+        }
+
+        /// Difference between the sum and the digits between the 1 and the 9. A line missing
+        /// either is already paid for by its house.
+        fn penalty(&self, grid: &Grid) -> u32 {
+            let Some((one, nine)) = self.crusts(grid) else {
+                return 0;
+            };
+            let total: u32 = between(&self.line.cells(), one, nine)
+                .iter()
+                .filter_map(|&(i, j)| grid[i][j])
+                .map(|num| num as u32)
+                .sum();
+            return total.abs_diff(self.sum);
+        }
+
+        /// Rules out a 1 or 9 where no place for the other leaves a filling that can reach the
+        /// sum, and once both are placed keeps only the filling digits that can reach it
+        fn propagate(&self, grid: &Grid, candidates: &mut Candidates) {
+            let cells = self.line.cells();
+            let masks = cells.map(|cell| mask_of(grid, candidates, cell));
+            let mask_at = |(i, j): (usize, usize)| candidates.get(i, j);
+            let could_be = |p: usize, digit: i8| masks[p] & digit_bit(digit) != 0;
+            let mut removed = Vec::new();
+            for (crust, other) in [(1, 9), (9, 1)] {
+                for (p, &(i, j)) in cells.iter().enumerate() {
+                    if grid[i][j].is_some() || !could_be(p, crust) {
+                        continue;
+                    }
+                    let paired = (0..BOARD_SIZE).any(|q| {
+                        q != p
+                            && could_be(q, other)
+                            && self.fits(grid, between(&cells, p, q), mask_at)
+                    });
+                    if !paired {
+                        removed.push((i, j, crust));
+                    }
+                }
+            }
+
+            if let Some((one, nine)) = self.crusts(grid) {
+                let filling = between(&cells, one, nine);
+                for &(i, j) in filling.iter().filter(|&&(i, j)| grid[i][j].is_none()) {
+                    for digit in 1..=BOARD_SIZE as i8 {
+                        // Try the filling with this cell fixed to the digit
+                        let fixed = |cell| {
+                            if cell == (i, j) {
+                                return digit_bit(digit);
+                            }
+                            return mask_at(cell);
+                        };
+                        if candidates.contains(i, j, digit) && !self.fits(grid, filling, fixed) {
+                            removed.push((i, j, digit));
+                        }
+                    }
+                }
+            }
+            for (i, j, digit) in removed {
+                candidates.eliminate(i, j, digit);
+            }
+        }
+
+        fn rule(&self) -> Option<String> {
+            return Some(format!("sandwich {}", self));
+        }
+    }
+
+    impl FromStr for Sandwich {
+        type Err = Box<dyn std::error::Error>;
+
+        /// A row or column and the sum, e.g. `r3 15` or `c5 0`
+        fn from_str(line: &str) -> Result<Self, Self::Err> {
+            let (name, sum) = line
+                .trim()
+                .split_once(' ')
+                .ok_or_else(|| format!("Sandwich should look like r3 15: {}", line))?;
+            let index = |v: &str| match v.parse::<usize>() {
+                Ok(v @ 1..=BOARD_SIZE) => Ok(v - 1),
+                _ => Err(format!("Sandwich line is off the board: {}", name)),
+            };
+            let which = match name.split_at_checked(1) {
+                Some(("r", v)) => Line::Row(index(v)?),
+                Some(("c", v)) => Line::Column(index(v)?),
+                _ => {
+                    return Err(Box::from(format!(
+                        "Sandwich should look like r3 15: {}",
+                        line
+                    )));
+                }
+            };
+            let sum = sum
+                .trim()
+                .parse()
+                .map_err(|_| format!("Sandwich sum is not a number: {}", line))?;
+            return Sandwich::new(which, sum);
+        }
+    }
+
+    impl Display for Sandwich {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            return match self.line {
+                Line::Row(i) => write!(f, "r{} {}", i + 1, self.sum),
+                Line::Column(j) => write!(f, "c{} {}", j + 1, self.sum),
+            };
+        }
+    }
+
+    /// A marker on the edge between two cells
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Marker {
+        /// Kropki white dot: the digits are consecutive
+        White,
+        /// Kropki black dot: one digit is double the other
+        Black,
+        /// The digits add up to 10
+        X,
+        /// The digits add up to 5
+        V,
+    }
+
+    impl Marker {
+        pub fn holds(self, a: i8, b: i8) -> bool {
+            return match self {
+                Marker::White => a.abs_diff(b) == 1,
+                Marker::Black => a == 2 * b || b == 2 * a,
+                Marker::X => a + b == 10,
+                Marker::V => a + b == 5,
+            };
+        }
+
+        /// Digits that can sit next to one of the digits in `mask` under this marker
+        fn partners(self, mask: u16) -> u16 {
+            let mut out = 0;
+            for a in (1..=BOARD_SIZE as i8).filter(|&a| mask & digit_bit(a) != 0) {
+                for b in 1..=BOARD_SIZE as i8 {
+                    if self.holds(a, b) {
+                        out |= digit_bit(b);
+                    }
+                }
+            }
+            return out;
+        }
+
+        fn name(self) -> &'static str {
+            return match self {
+                Marker::White => "white",
+                Marker::Black => "black",
+                Marker::X => "x",
+                Marker::V => "v",
+            };
+        }
+    }
+
+    impl FromStr for Marker {
+        type Err = Box<dyn std::error::Error>;
+
+        fn from_str(name: &str) -> Result<Self, Self::Err> {
+            return match name {
+                "white" => Ok(Marker::White),
+                "black" => Ok(Marker::Black),
+                "x" => Ok(Marker::X),
+                "v" => Ok(Marker::V),
+                _ => Err(Box::from(format!("Unknown marker: {}", name))),
+            };
+        }
+    }
+
+    impl Display for Marker {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            return write!(f, "{}", self.name());
+        }
+    }
+
+    /// A marker between two orthogonally adjacent cells
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Dot {
+        pub marker: Marker,
+        pub cells: [(usize, usize); 2],
+    }
+
+    impl FromStr for Dot {
+        type Err = Box<dyn std::error::Error>;
+
+        /// The marker and its two cells, e.g. `white r1c1 r1c2` or `x r4c5 r5c5`
+        fn from_str(line: &str) -> Result<Self, Self::Err> {
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            let [marker, a, b] = parts[..] else {
+                return Err(Box::from(format!(
+                    "Marker should look like white r1c1 r1c2: {}",
+                    line
+                )));
+            };
+            return Ok(Dot {
+                marker: marker.parse()?,
+                cells: [parse_cell(a)?, parse_cell(b)?],
+            });
+        }
+    }
+
+    impl Display for Dot {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{} ", self.marker)?;
+            return write_cells(f, &self.cells);
+        }
+    }
+
+    /// Kropki dots and XV markers. Under the negative constraint, markers listed in `negative`
+    /// are all given, so no unmarked pair of neighbours may satisfy them.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Markers {
+        dots: Vec<Dot>,
+        negative: Vec<Marker>,
+        /// Neighbouring pairs without a dot, only filled in when `negative` isn't empty
+        unmarked: Vec<[(usize, usize); 2]>,
+    }
+
+    impl Markers {
+        /// Negative markers come in whole families, white with black and x with v, as the
+        /// `negative kropki` and `negative xv` rules give them
+        pub fn new(
+            dots: Vec<Dot>,
+            negative: Vec<Marker>,
+        ) -> Result<Self, Box<dyn std::error::Error>> {
+            for marker in &negative {
+                let family = match marker {
+                    Marker::White | Marker::Black => [Marker::White, Marker::Black],
+                    Marker::X | Marker::V => [Marker::X, Marker::V],
+                };
+                if !family.iter().all(|other| negative.contains(other)) {
+                    return Err(Box::from(format!(
+                        "Negative {} needs both {} and {}",
+                        marker, family[0], family[1]
+                    )));
+                }
+            }
+            for (k, dot) in dots.iter().enumerate() {
+                let [(i1, j1), (i2, j2)] = dot.cells;
+                if dot
+                    .cells
+                    .iter()
+                    .any(|&(i, j)| i >= BOARD_SIZE || j >= BOARD_SIZE)
+                {
+                    return Err(Box::from(format!("Marker is off the board: {}", dot)));
+                }
+                if i1.abs_diff(i2) + j1.abs_diff(j2) != 1 {
+                    return Err(Box::from(format!(
+                        "Marker cells aren't neighbours: {}",
+                        dot
+                    )));
+                }
+                if dots[..k]
+                    .iter()
+                    .any(|other| same_edge(other.cells, dot.cells))
+                {
+                    return Err(Box::from(format!("Two markers on one edge: {}", dot)));
+                }
+            }
+            let mut unmarked = Vec::new();
+            if !negative.is_empty() {
+                for i in 0..BOARD_SIZE {
+                    for j in 0..BOARD_SIZE {
+                        for pair in [[(i, j), (i, j + 1)], [(i, j), (i + 1, j)]] {
+                            let on_board = pair[1].0 < BOARD_SIZE && pair[1].1 < BOARD_SIZE;
+                            if on_board && !dots.iter().any(|dot| same_edge(dot.cells, pair)) {
+                                unmarked.push(pair);
+                            }
+                        }
+                    }
+                }
+            }
+            return Ok(Markers {
+                dots,
+                negative,
+                unmarked,
+            });
+        }
+
+        pub fn dots(&self) -> &[Dot] {
+            return &self.dots;
+        }
+
+        pub fn negative(&self) -> &[Marker] {
+            return &self.negative;
+        }
+
+        /// Dots with both digits placed that don't hold
+        fn broken_dots<'a>(&'a self, grid: &'a Grid) -> impl Iterator<Item = Violation> + 'a {
+            return self.dots.iter().filter_map(|dot| {
+                let [(i1, j1), (i2, j2)] = dot.cells;
+                let (a, b) = (grid[i1][j1]?, grid[i2][j2]?);
+                if dot.marker.holds(a, b) {
+                    return None;
+                }
+                return Some(Violation {
+                    rule: format!("{} marker", dot.marker),
+                    cells: dot.cells.to_vec(),
+                });
+            });
+        }
+
+        /// Unmarked pairs with both digits placed that satisfy a marker known to be missing
+        fn missing_dots<'a>(&'a self, grid: &'a Grid) -> impl Iterator<Item = Violation> + 'a {
+            return self.unmarked.iter().filter_map(|&[(i1, j1), (i2, j2)]| {
+                let (a, b) = (grid[i1][j1]?, grid[i2][j2]?);
+                let marker = self.negative.iter().find(|marker| marker.holds(a, b))?;
+                return Some(Violation {
+                    rule: format!("missing {} marker", marker),
+                    cells: vec![(i1, j1), (i2, j2)],
+                });
+            });
+        }
+    }
+
+    fn same_edge(a: [(usize, usize); 2], b: [(usize, usize); 2]) -> bool {
+        return a == b || a == [b[1], b[0]];
+    }
+
+    impl Constraint for Markers {
+        fn violations(&self, grid: &Grid) -> Vec<Violation> {
+            return self
+                .broken_dots(grid)
+                .chain(self.missing_dots(grid))
+                .collect();
+        }
+
+        /// Number of broken or missing markers
+        fn penalty(&self, grid: &Grid) -> u32 {
+            return (self.broken_dots(grid).count() + self.missing_dots(grid).count()) as u32;
+        }
+
+        /// Keeps digits with a partner on the other side of each dot, and drops digits that
+        /// would need a missing marker next to a placed digit
+        fn propagate(&self, grid: &Grid, candidates: &mut Candidates) {
+            for dot in &self.dots {
+                let [a, b] = dot.cells;
+                let (mask_a, mask_b) = (mask_of(grid, candidates, a), mask_of(grid, candidates, b));
+                if grid[a.0][a.1].is_none() {
+                    candidates.retain(a.0, a.1, dot.marker.partners(mask_b));
+                }
+                if grid[b.0][b.1].is_none() {
+                    candidates.retain(b.0, b.1, dot.marker.partners(mask_a));
+                }
+            }
+            for &[a, b] in &self.unmarked {
+                let (empty, num) = match (grid[a.0][a.1], grid[b.0][b.1]) {
+                    (Some(num), None) => (b, num),
+                    (None, Some(num)) => (a, num),
+                    _ => continue,
+                };
+                for marker in &self.negative {
+                    candidates.retain(empty.0, empty.1, !marker.partners(digit_bit(num)));
+                }
+            }
+        }
+
+        fn is_violated(&self, grid: &Grid) -> bool {
+            return self.broken_dots(grid).next().is_some()
+                || self.missing_dots(grid).next().is_some();
+        }
+
+        /// One line per dot, then the negative constraints
+        fn rule(&self) -> Option<String> {
+            let mut lines: Vec<String> = self.dots.iter().map(Dot::to_string).collect();
+            if self.negative.contains(&Marker::White) {
+                lines.push("negative kropki".to_string());
+            }
+            if self.negative.contains(&Marker::X) {
+                lines.push("negative xv".to_string());
+            }
+            return Some(lines.join("\n"));
+        }
+    }
+
+    const ODD_DIGITS: u16 = 0b1_0101_0101;
+    const EVEN_DIGITS: u16 = 0b0_1010_1010;
+
+    /// Cells shaded as odd or even
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Parity {
+        even: bool,
+        cells: Vec<(usize, usize)>,
+    }
+
+    impl Parity {
+        pub fn odd(cells: Vec<(usize, usize)>) -> Self {
+            return Parity { even: false, cells };
+        }
+
+        pub fn even(cells: Vec<(usize, usize)>) -> Self {
+            return Parity { even: true, cells };
+        }
+
+        pub fn is_even(&self) -> bool {
+            return self.even;
+        }
+
+        pub fn cells(&self) -> &[(usize, usize)] {
+            return &self.cells;
+        }
+
+        fn digits(&self) -> u16 {
+            return if self.even { EVEN_DIGITS } else { ODD_DIGITS };
+        }
+
+        fn wrong_cells<'a>(&'a self, grid: &'a Grid) -> impl Iterator<Item = (usize, usize)> + 'a {
+            return self.cells.iter().copied().filter(|&(i, j)| {
+                grid[i][j].is_some_and(|num| self.digits() & digit_bit(num) == 0)
+            });
+        }
+    }
+
+    impl Constraint for Parity {
+        fn violations(&self, grid: &Grid) -> Vec<Violation> {
+            let rule = if self.even { "even cell" } else { "odd cell" };
+            return self
+                .wrong_cells(grid)
+                .map(|cell| Violation {
+                    rule: rule.to_string(),
+                    cells: vec![cell],
+                })
+                .collect();
+        }
+
+        /// Number of cells with the wrong parity
+        fn penalty(&self, grid: &Grid) -> u32 {
+            return self.wrong_cells(grid).count() as u32;
+        }
+
+        fn propagate(&self, grid: &Grid, candidates: &mut Candidates) {
+            for &(i, j) in &self.cells {
+                if grid[i][j].is_none() {
+                    candidates.retain(i, j, self.digits());
+                }
+            }
+        }
+
+        fn is_violated(&self, grid: &Grid) -> bool {
+            return self.wrong_cells(grid).next().is_some();
+        }
+
+        fn rule(&self) -> Option<String> {
+            return Some(self.to_string());
+        }
+    }
+
+    impl FromStr for Parity {
+        type Err = Box<dyn std::error::Error>;
+
+        /// `odd` or `even`, then the cells, e.g. `odd r1c1 r5c5`
+        fn from_str(line: &str) -> Result<Self, Self::Err> {
+            let mut parts = line.split_whitespace();
+            let even = match parts.next() {
+                Some("odd") => false,
+                Some("even") => true,
+                _ => {
+                    return Err(Box::from(format!(
+                        "Parity should look like odd r1c1: {}",
+                        line
+                    )));
+                }
+            };
+            let cells = parts.map(parse_cell).collect::<Result<Vec<_>, String>>()?;
+            if cells.is_empty() {
+                return Err(Box::from(format!("Parity rule has no cells: {}", line)));
+            }
+            return Ok(Parity { even, cells });
+        }
+    }
+
+    impl Display for Parity {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{} ", if self.even { "even" } else { "odd" })?;
+            return write_cells(f, &self.cells);
+        }
+    }
+
+    /// A greater-than sign between two neighbouring cells
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Inequality {
+        greater: (usize, usize),
+        less: (usize, usize),
+    }
+
+    impl Inequality {
+        pub fn new(
+            greater: (usize, usize),
+            less: (usize, usize),
+        ) -> Result<Self, Box<dyn std::error::Error>> {
+            if greater.0.abs_diff(less.0) + greater.1.abs_diff(less.1) != 1 {
+                return Err(Box::from(format!(
+                    "Inequality cells ({},{}) and ({},{}) aren't neighbours",
+                    greater.0, greater.1, less.0, less.1
+                )));
+            }
+            return Ok(Inequality { greater, less });
+        }
+
+        pub fn greater(&self) -> (usize, usize) {
+            return self.greater;
+        }
+
+        pub fn less(&self) -> (usize, usize) {
+            return self.less;
+        }
+
+        fn digits(&self, grid: &Grid) -> Option<(i8, i8)> {
+            let (g, l) = (self.greater, self.less);
+            return Some((grid[g.0][g.1]?, grid[l.0][l.1]?));
+        }
+    }
+
+    impl Constraint for Inequality {
+        fn violations(&self, grid: &Grid) -> Vec<Violation> {
+            if self.is_violated(grid) {
+                return vec![Violation {
+                    rule: "inequality".to_string(),
+                    cells: vec![self.greater, self.less],
+                }];
+            }
+            return Vec::new();
+        }
+
+        /// How far the greater cell falls short of being one more than the other
+        fn penalty(&self, grid: &Grid) -> u32 {
+            return self
+                .digits(grid)
+                .map_or(0, |(greater, less)| (less - greater + 1).max(0) as u32);
+        }
+
+        fn propagate(&self, grid: &Grid, candidates: &mut Candidates) {
+            let (g, l) = (self.greater, self.less);
+            let (greater, less) = (mask_of(grid, candidates, g), mask_of(grid, candidates, l));
+            if grid[g.0][g.1].is_none() {
+                candidates.retain(g.0, g.1, digits_between(lowest_digit(less) + 1, 9));
+            }
+            if grid[l.0][l.1].is_none() {
+                candidates.retain(l.0, l.1, digits_between(1, highest_digit(greater) - 1));
+            }
+        }
+
+        fn is_violated(&self, grid: &Grid) -> bool {
+            return self
+                .digits(grid)
+                .is_some_and(|(greater, less)| greater <= less);
+        }
+
+        fn rule(&self) -> Option<String> {
+            return Some(format!("greater {}", self));
+        }
+    }
+
+    impl FromStr for Inequality {
+        type Err = Box<dyn std::error::Error>;
+
+        /// The greater cell, then the smaller one, e.g. `r1c1 r1c2`
+        fn from_str(line: &str) -> Result<Self, Self::Err> {
+            let cells = line
+                .split_whitespace()
+                .map(parse_cell)
+                .collect::<Result<Vec<_>, String>>()?;
+            let [greater, less] = cells[..] else {
+                return Err(Box::from(format!(
+                    "Inequality should look like r1c1 r1c2: {}",
+                    line
+                )));
+            };
+            return Inequality::new(greater, less);
+        }
+    }
+
+    impl Display for Inequality {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            return write_cells(f, &[self.greater, self.less]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::constraint::constraint::{Candidates, Constraint, digit_bit};
+    use crate::exact::exact::{count_solutions, solve};
+    use crate::rules::rules::{apply_rules, rule_lines};
+    use crate::sudoku::sudoku::{BOARD_SIZE, Board, Solution};
+    use crate::variants::variants::{
+        AntiKing, AntiKnight, Arrow, Dot, Inequality, Marker, Markers, Parity, Sandwich,
+        Thermometer,
+    };
+
+    #[test]
+    fn checks_chess_moves() {
+        let mut board = Board::default();
+        board.set(4, 4, Some(5));
+        board.set(6, 5, Some(5));
+        assert_eq!(AntiKnight.penalty(&board.grid), 1);
+        assert_eq!(
+            AntiKnight.violations(&board.grid)[0].cells,
+            vec![(4, 4), (6, 5)]
+        );
+        assert!(!AntiKing.is_violated(&board.grid));
+
+        board.set(5, 5, Some(5));
+        assert!(AntiKing.is_violated(&board.grid));
+
+        let mut candidates = Candidates::all();
+        board.set(5, 5, None);
+        board.set(6, 5, None);
+        AntiKnight.propagate(&board.grid, &mut candidates);
+        AntiKing.propagate(&board.grid, &mut candidates);
+        assert!(!candidates.contains(2, 3, 5));
+        assert!(!candidates.contains(3, 3, 5));
+        assert!(candidates.contains(2, 2, 5));
+    }
+
+    #[test]
+    fn solves_anti_knight_and_anti_king() {
+        let mut board = Board::default();
+        board.add_constraint(AntiKnight);
+        board.add_constraint(AntiKing);
+
+        let solved = solve(&board).unwrap();
+        assert!(!AntiKnight.is_violated(&solved.grid));
+        assert!(!AntiKing.is_violated(&solved.grid));
+        let score = board
+            .score_solution(&Solution::from_board(solved).unwrap())
+            .unwrap();
+        assert_eq!(score, 0);
+
+        board.set(0, 0, Some(1));
+        board.set(1, 2, Some(1));
+        assert_eq!(count_solutions(&board, 1), 0);
+    }
+
+    const SOLVED: &str =
+        "278395461649182357315746928432859716786213549591467832954628173863571294127934685";
+
+    #[test]
+    fn checks_thermometers_arrows_and_sandwiches() {
+        let solved = Board::from_string(SOLVED).unwrap();
+        let thermo: Thermometer = "r1c1 r1c2 r1c3".parse().unwrap();
+        let arrow: Arrow = "r1c5 r1c6 r1c7".parse().unwrap();
+        let sandwich: Sandwich = "r1 15".parse().unwrap();
+        assert!(!thermo.is_violated(&solved.grid));
+        assert!(!arrow.is_violated(&solved.grid));
+        assert!(!sandwich.is_violated(&solved.grid));
+        assert_eq!(thermo.to_string(), "r1c1 r1c2 r1c3");
+        assert_eq!(arrow.to_string(), "r1c5 r1c6 r1c7");
+        assert_eq!(sandwich.to_string(), "r1 15");
+
+        let thermo: Thermometer = "r1c3 r1c2".parse().unwrap();
+        assert_eq!(thermo.penalty(&solved.grid), 2);
+        let arrow: Arrow = "r1c5 r1c6".parse().unwrap();
+        assert_eq!(arrow.penalty(&solved.grid), 4);
+        let sandwich: Sandwich = "c1 7".parse().unwrap();
+        assert_eq!(sandwich.penalty(&solved.grid), 1);
+        assert_eq!(sandwich.violations(&solved.grid)[0].cells, [(8, 0), (6, 0)]);
+
+        assert!("r1c1".parse::<Thermometer>().is_err());
+        assert!("r1c1 r3c3".parse::<Thermometer>().is_err());
+        assert!("r1c1 r1c2 r1c1".parse::<Arrow>().is_err());
+        assert!("r1 36".parse::<Sandwich>().is_err());
+        assert!("x1 3".parse::<Sandwich>().is_err());
+    }
+
+    #[test]
+    fn propagates_lines() {
+        let board = Board::default();
+        let mut candidates = Candidates::all();
+        let long: Thermometer = "r1c1 r1c2 r1c3 r1c4 r1c5 r1c6 r1c7 r1c8 r1c9"
+            .parse()
+            .unwrap();
+        long.propagate(&board.grid, &mut candidates);
+        for j in 0..BOARD_SIZE {
+            assert_eq!(candidates.get(0, j), 1 << j);
+        }
+
+        let arrow: Arrow = "r5c5 r6c6 r7c7 r8c8".parse().unwrap();
+        arrow.propagate(&board.grid, &mut candidates);
+        assert_eq!(candidates.get(4, 4), 0b1_1111_1100);
+        assert_eq!(candidates.get(5, 5), 0b0_0111_1111);
+
+        let mut candidates = Candidates::all();
+        let full: Sandwich = "c9 35".parse().unwrap();
+        full.propagate(&board.grid, &mut candidates);
+        assert!(candidates.contains(0, 8, 1));
+        assert!(candidates.contains(8, 8, 9));
+        assert!(!candidates.contains(4, 8, 1));
+        assert!(!candidates.contains(1, 8, 9));
+    }
+
+    #[test]
+    fn solves_with_lines() {
+        let mut board = Board::from_string(
+            "008090060009000000000000020000800000700000009000000000000000000000070090000000000",
+        )
+        .unwrap();
+        let thermo: Thermometer = "r1c1 r1c2 r1c3".parse().unwrap();
+        let arrow: Arrow = "r1c5 r1c6 r1c7".parse().unwrap();
+        let row: Sandwich = "r1 15".parse().unwrap();
+        let column: Sandwich = "c1 8".parse().unwrap();
+        board.add_constraint(thermo.clone());
+        board.add_constraint(arrow.clone());
+        board.add_constraint(row);
+        board.add_constraint(column);
+
+        let solved = solve(&board).unwrap();
+        assert!(!thermo.is_violated(&solved.grid));
+        assert!(!arrow.is_violated(&solved.grid));
+        assert!(!row.is_violated(&solved.grid));
+        assert!(!column.is_violated(&solved.grid));
+        let score: u32 = board.constraints().map(|c| c.penalty(&solved.grid)).sum();
+        assert_eq!(score, 0);
+    }
+
+    #[test]
+    fn checks_markers() {
+        assert!(Marker::White.holds(4, 5));
+        assert!(Marker::Black.holds(6, 3));
+        assert!(Marker::X.holds(3, 7));
+        assert!(!Marker::V.holds(2, 2));
+
+        let dot: Dot = "black r1c1 r1c2".parse().unwrap();
+        assert_eq!(dot.to_string(), "black r1c1 r1c2");
+        assert!(Markers::new(vec!["x r1c1 r2c2".parse().unwrap()], Vec::new()).is_err());
+        assert!(Markers::new(vec![dot, "v r1c2 r1c1".parse().unwrap()], Vec::new()).is_err());
+        let off_board = Dot {
+            marker: Marker::X,
+            cells: [(8, 0), (9, 0)],
+        };
+        assert!(Markers::new(vec![off_board], Vec::new()).is_err());
+
+        // Only whole families of negative markers can be written back as rules
+        assert!(Markers::new(Vec::new(), vec![Marker::Black]).is_err());
+        assert!(Markers::new(Vec::new(), vec![Marker::V]).is_err());
+        let xv = Markers::new(vec![dot], vec![Marker::V, Marker::X]).unwrap();
+        let mut board = Board::default();
+        board.add_constraint(xv);
+        let lines = rule_lines(&board);
+        assert_eq!(lines, ["black r1c1 r1c2", "negative xv"]);
+        let mut copy = Board::default();
+        apply_rules(&mut copy, lines.iter().map(String::as_str)).unwrap();
+        assert_eq!(rule_lines(&copy), lines);
+
+        let markers = Markers::new(vec![dot], vec![Marker::White, Marker::Black]).unwrap();
+        let mut board = Board::default();
+        board.set(0, 0, Some(4));
+        board.set(0, 1, Some(8));
+        board.set(1, 0, Some(5));
+        assert_eq!(
+            markers.violations(&board.grid)[0].rule,
+            "missing white marker"
+        );
+        board.set(1, 0, Some(7));
+        assert!(!markers.is_violated(&board.grid));
+
+        let mut candidates = Candidates::all();
+        board.set(0, 1, None);
+        markers.propagate(&board.grid, &mut candidates);
+        assert_eq!(candidates.get(0, 1), digit_bit(2) | digit_bit(8));
+        assert!(!candidates.contains(2, 0, 6));
+        assert!(!candidates.contains(2, 0, 8));
+        assert!(candidates.contains(2, 0, 9));
+    }
+
+    #[test]
+    fn solves_negative_kropki() {
+        let solved = Board::from_string(SOLVED).unwrap();
+        let mut dots = Vec::new();
+        for i in 0..BOARD_SIZE {
+            for j in 0..BOARD_SIZE {
+                for (i2, j2) in [(i, j + 1), (i + 1, j)] {
+                    if i2 == BOARD_SIZE || j2 == BOARD_SIZE {
+                        continue;
+                    }
+                    let (a, b) = (solved.get(i, j).unwrap(), solved.get(i2, j2).unwrap());
+                    for marker in [Marker::White, Marker::Black] {
+                        if marker.holds(a, b) {
+                            dots.push(Dot {
+                                marker,
+                                cells: [(i, j), (i2, j2)],
+                            });
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut board = Board::default();
+        let markers = Markers::new(dots, vec![Marker::White, Marker::Black]).unwrap();
+        assert_eq!(markers.penalty(&solved.grid), 0);
+        board.add_constraint(markers);
+        assert_eq!(count_solutions(&board, 2), 1);
+        assert_eq!(solve(&board).unwrap().to_clean_string(), SOLVED);
+    }
+
+    #[test]
+    fn checks_parity_and_inequalities() {
+        let odd: Parity = "odd r1c1 r1c2".parse().unwrap();
+        let sign: Inequality = "r2c1 r2c2".parse().unwrap();
+        assert!(!odd.is_even());
+        assert!("r2c1 r3c2".parse::<Inequality>().is_err());
+
+        let mut board = Board::default();
+        board.set(0, 0, Some(4));
+        board.set(0, 1, Some(3));
+        board.set(1, 0, Some(2));
+        board.set(1, 1, Some(5));
+        assert_eq!(odd.violations(&board.grid)[0].cells, vec![(0, 0)]);
+        assert_eq!(odd.penalty(&board.grid), 1);
+        assert_eq!(sign.penalty(&board.grid), 4);
+        assert!(sign.is_violated(&board.grid));
+        board.set(1, 0, Some(6));
+        assert!(!sign.is_violated(&board.grid));
+
+        let mut candidates = Candidates::all();
+        board.set(0, 0, None);
+        board.set(1, 1, None);
+        odd.propagate(&board.grid, &mut candidates);
+        sign.propagate(&board.grid, &mut candidates);
+        assert_eq!(candidates.get(0, 0), 0b1_0101_0101);
+        assert_eq!(candidates.get(1, 1), 0b1_1111);
+    }
+
+    #[test]
+    fn solves_greater_than() {
+        // A sign between every pair of neighbours in the same box, with the first row shaded
+        let solved = Board::from_string(SOLVED).unwrap();
+        let mut board = Board::default();
+        for i in 0..BOARD_SIZE {
+            for j in 0..BOARD_SIZE {
+                for (i2, j2) in [(i, j + 1), (i + 1, j)] {
+                    if i2 % 3 == 0 && i2 != i || j2 % 3 == 0 && j2 != j {
+                        continue;
+                    }
+                    let (a, b) = ((i, j), (i2, j2));
+                    let (greater, less) = if solved.get(i, j) > solved.get(i2, j2) {
+                        (a, b)
+                    } else {
+                        (b, a)
+                    };
+                    board.add_constraint(Inequality::new(greater, less).unwrap());
+                }
+            }
+        }
+        let (even, odd): (Vec<_>, Vec<_>) = (0..BOARD_SIZE)
+            .map(|j| (0, j))
+            .partition(|&(i, j)| solved.get(i, j).unwrap() % 2 == 0);
+        board.add_constraint(Parity::even(even));
+        board.add_constraint(Parity::odd(odd));
+
+        let score = board
+            .score_solution(&Solution::from_board(solved.clone()).unwrap())
+            .unwrap();
+        assert_eq!(score, 0);
+
+        // The signs leave a few solutions open, three givens settle it
+        assert!(count_solutions(&board, 2) > 1);
+        for (i, j) in [(4, 4), (5, 5), (8, 0)] {
+            board.set(i, j, solved.get(i, j));
+        }
+        assert_eq!(count_solutions(&board, 2), 1);
+        assert_eq!(solve(&board).unwrap().to_clean_string(), SOLVED);
+    }
+}
@@ -1,4 +1,11 @@
+#![allow(clippy::needless_return)]
+
 //use soducant_core::solver;
+use soducant_core::constraint::constraint::Candidates;
+use soducant_core::save::save;
+use soducant_core::session::session::GameSession;
+use soducant_core::sudoku::sudoku::{BOARD_SIZE, Board};
+use std::time::Duration;
 use wasm_bindgen::prelude::*;
 
 #[wasm_bindgen]
@@ -21,3 +28,119 @@ pub fn benchmark_intern() {
             });
     }
 }
+
+/// Game state for the web page, backed by the core `GameSession`
+#[wasm_bindgen]
+pub struct Game {
+    session: GameSession,
+}
+
+#[wasm_bindgen]
+impl Game {
+    #[wasm_bindgen(constructor)]
+    pub fn new(puzzle: &str) -> Result<Game, String> {
+        let board = Board::from_string(puzzle).map_err(|e| e.to_string())?;
+        let session = GameSession::new(board).map_err(|e| e.to_string())?;
+        return Ok(Game { session });
+    }
+
+    /// Digit in the cell, 0 when empty
+    pub fn value(&self, row: usize, col: usize) -> Result<u8, String> {
+        let value = self.session.value(row, col).map_err(|e| e.to_string())?;
+        return Ok(value.unwrap_or(0) as u8);
+    }
+
+    pub fn is_given(&self, row: usize, col: usize) -> Result<bool, String> {
+        return self.session.is_given(row, col).map_err(|e| e.to_string());
+    }
+
+    /// Pencil marks as a bitmask, bit `d - 1` for digit `d`
+    pub fn notes(&self, row: usize, col: usize) -> Result<u16, String> {
+        let cell = self.session.cell(row, col).map_err(|e| e.to_string())?;
+        return Ok(cell.notes);
+    }
+
+    /// Candidates of every cell in reading order, as bitmasks like `notes`, for showing
+    /// automatic notes. Filled cells have none.
+    pub fn auto_notes(&self) -> Vec<u16> {
+        let candidates = Candidates::from_board(&self.session.board());
+        return (0..BOARD_SIZE * BOARD_SIZE)
+            .map(|k| candidates.get(k / BOARD_SIZE, k % BOARD_SIZE))
+            .collect();
+    }
+
+    /// Enters a digit, or clears the entry with 0
+    pub fn set_value(&mut self, row: usize, col: usize, digit: u8) -> Result<(), String> {
+        let value = if digit == 0 { None } else { Some(digit as i8) };
+        return self
+            .session
+            .set_value(row, col, value)
+            .map_err(|e| e.to_string());
+    }
+
+    pub fn toggle_note(&mut self, row: usize, col: usize, digit: u8) -> Result<(), String> {
+        return self
+            .session
+            .toggle_note(row, col, digit as i8)
+            .map_err(|e| e.to_string());
+    }
+
+    /// Returns false when there was nothing to undo
+    pub fn undo(&mut self) -> bool {
+        return self.session.undo().is_some();
+    }
+
+    /// Returns false when there was nothing to redo
+    pub fn redo(&mut self) -> bool {
+        return self.session.redo().is_some();
+    }
+
+    pub fn mistakes(&self) -> u32 {
+        return self.session.mistakes();
+    }
+
+    pub fn is_mistake(&self, row: usize, col: usize) -> Result<bool, String> {
+        return self.session.is_mistake(row, col).map_err(|e| e.to_string());
+    }
+
+    /// Whether the cell repeats a digit in its row, column, region or another house
+    pub fn is_conflict(&self, row: usize, col: usize) -> Result<bool, String> {
+        return self
+            .session
+            .is_conflict(row, col)
+            .map_err(|e| e.to_string());
+    }
+
+    pub fn is_solved(&self) -> bool {
+        return self.session.is_solved();
+    }
+
+    pub fn tick(&mut self, ms: f64) {
+        self.session
+            .tick(Duration::from_secs_f64(ms.max(0.0) / 1000.0));
+    }
+
+    pub fn elapsed_ms(&self) -> f64 {
+        return self.session.elapsed().as_secs_f64() * 1000.0;
+    }
+
+    /// Givens and entries as an 81-character string
+    pub fn board_string(&self) -> String {
+        return self.session.board().to_clean_string();
+    }
+
+    /// Compact text save, suitable for localStorage. Variant rules are saved with the givens.
+    pub fn save(&self) -> String {
+        return save::to_text(&self.session);
+    }
+
+    pub fn save_json(&self) -> String {
+        return save::to_json(&self.session);
+    }
+
+    /// Restores a game from either save format
+    pub fn load(text: &str) -> Result<Game, String> {
+        let session = save::load(text).map_err(|e| e.to_string())?;
+        return Ok(Game { session });
+    }
+}
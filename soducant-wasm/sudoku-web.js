@@ -2,12 +2,16 @@ import init, {
   solve,
   get_random_puzzle,
   benchmark_intern,
+  Game,
 } from "./pkg/soducant_wasm.js";
 
 var solutionVisible = false;
 var allEntries = [];
+var game = null;
 
-init();
+const SAVE_KEY = "soducant-save";
+
+init().then(resumeSavedGame);
 
 function generateSudokuBoard() {
   const table = document.getElementById("sudokuBoard");
@@ -36,10 +40,18 @@ function generateSudokuBoard() {
 
       // Handle clearing inputs on entry
       input.addEventListener("input", clearPlaceholders);
+      input.addEventListener("input", function () {
+        onCellInput(row - 1, col - 1, input);
+      });
       allEntries.push(input);
 
       // Handle moving to new boxes on input
       input.addEventListener("keydown", function (e) {
+        if (e.ctrlKey && (e.key == "z" || e.key == "y")) {
+          e.preventDefault();
+          undoRedo(e.key == "z");
+          return;
+        }
         var nextInputId = 0;
         switch (e.key) {
           case "ArrowUp":
@@ -125,6 +137,7 @@ function readSudoku() {
 }
 
 function writeSudoku(puzzle) {
+  endGame();
   for (var i = 1; i < 82; i++) {
     if (puzzle[i - 1] == 0) {
       document.getElementById("s" + i).value = "";
@@ -165,9 +178,114 @@ function solveSudoku() {
 
 function generateNewBoard() {
   clearPlaceholders();
-  writeSudoku(get_random_puzzle());
+  const puzzle = get_random_puzzle();
+  writeSudoku(puzzle);
+  startGame(puzzle);
+}
+
+// Game state lives in the core GameSession; the page only mirrors it
+function startGame(puzzle) {
+  showGame(new Game(puzzle));
+  saveGame();
 }
 
+function showGame(newGame) {
+  game = newGame;
+  const board = game.board_string();
+  for (var i = 0; i < 81; i++) {
+    allEntries[i].value = board[i] == "0" ? "" : board[i];
+    allEntries[i].readOnly = game.is_given(Math.floor(i / 9), i % 9);
+  }
+  updateStatus();
+}
+
+function saveGame() {
+  if (game != null) {
+    localStorage.setItem(SAVE_KEY, game.save());
+  }
+}
+
+function resumeSavedGame() {
+  const saved = localStorage.getItem(SAVE_KEY);
+  if (saved == null) {
+    return;
+  }
+  try {
+    showGame(Game.load(saved));
+  } catch (e) {
+    // Saves from an incompatible version are dropped
+    localStorage.removeItem(SAVE_KEY);
+  }
+}
+
+function endGame() {
+  if (game != null) {
+    game.free();
+    game = null;
+    localStorage.removeItem(SAVE_KEY);
+  }
+  allEntries.forEach((el) => {
+    el.readOnly = false;
+  });
+  updateStatus();
+}
+
+function onCellInput(row, col, input) {
+  if (game == null) {
+    return;
+  }
+  const digit = input.value == "" ? 0 : parseInt(input.value);
+  try {
+    game.set_value(row, col, digit);
+  } catch (e) {
+    input.value = "";
+  }
+  saveGame();
+  updateStatus();
+}
+
+function undoRedo(undo) {
+  if (game == null) {
+    return;
+  }
+  if (undo ? game.undo() : game.redo()) {
+    const board = game.board_string();
+    for (var i = 0; i < 81; i++) {
+      allEntries[i].value = board[i] == "0" ? "" : board[i];
+    }
+  }
+  saveGame();
+  updateStatus();
+}
+
+function updateStatus() {
+  const status = document.getElementById("status");
+  if (status == null) {
+    return;
+  }
+  if (game == null) {
+    status.innerHTML = "";
+    return;
+  }
+  const seconds = Math.floor(game.elapsed_ms() / 1000);
+  status.innerHTML =
+    "Time " +
+    Math.floor(seconds / 60) +
+    ":" +
+    String(seconds % 60).padStart(2, "0") +
+    ", mistakes: " +
+    game.mistakes() +
+    (game.is_solved() ? " - solved!" : "");
+}
+
+setInterval(function () {
+  if (game != null) {
+    game.tick(1000);
+    saveGame();
+    updateStatus();
+  }
+}, 1000);
+
 async function run_web_benchmark() {
   const tries = 1000;
 